@@ -0,0 +1,334 @@
+//! Interchangeable storage drivers behind the `KVStore` trait.
+//!
+//! Each backend is gated by its own cargo feature and exposes the same
+//! `begin_transaction`/`commit` semantics `SMTStore` relies on, so callers can
+//! pick a driver based on their RAM/disk tradeoffs instead of being locked to
+//! RocksDB. Columns are emulated by prefixing keys with the 1-byte `Col`.
+
+use crate::db::Col;
+use crate::error::Error;
+use crate::traits::kv_store::{KVStore, KVStoreRead, KVStoreScan, KVStoreWrite};
+
+/// Prefix a key with its column so a single key space emulates column families.
+fn col_key(col: Col, key: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1 + key.len());
+    out.push(col);
+    out.extend_from_slice(key);
+    out
+}
+
+/// The smallest key strictly greater than every key with `prefix`, or `None`
+/// when `prefix` is empty or all `0xff` (no finite upper bound).
+#[allow(dead_code)]
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut bound = prefix.to_vec();
+    while let Some(last) = bound.last_mut() {
+        if *last < 0xff {
+            *last += 1;
+            return Some(bound);
+        }
+        bound.pop();
+    }
+    None
+}
+
+#[cfg(feature = "sled-backend")]
+pub mod sled_backend {
+    use super::*;
+
+    /// A `KVStore` backed by [`sled`].
+    pub struct SledStore {
+        db: sled::Db,
+    }
+
+    impl SledStore {
+        pub fn open(path: &std::path::Path) -> Result<Self, Error> {
+            let db = sled::open(path).map_err(|e| Error::Store(e.to_string()))?;
+            Ok(SledStore { db })
+        }
+
+        /// Open a write transaction. sled batches are applied atomically on commit.
+        pub fn begin_transaction(&self) -> SledTransaction<'_> {
+            SledTransaction {
+                db: &self.db,
+                batch: std::cell::RefCell::new(sled::Batch::default()),
+            }
+        }
+    }
+
+    impl KVStoreRead for SledStore {
+        fn get(&self, col: Col, key: &[u8]) -> Option<bytes::Bytes> {
+            self.db
+                .get(col_key(col, key))
+                .expect("db operation should be ok")
+                .map(|v| bytes::Bytes::copy_from_slice(v.as_ref()))
+        }
+    }
+
+    impl KVStoreScan for SledStore {
+        fn scan_prefix<'a>(
+            &'a self,
+            col: Col,
+            prefix: &[u8],
+        ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+            // sled scans in key order; strip the 1-byte column prefix we prepend.
+            Box::new(
+                self.db
+                    .scan_prefix(col_key(col, prefix))
+                    .filter_map(|r| r.ok())
+                    .map(|(k, v)| (Box::<[u8]>::from(&k[1..]), Box::<[u8]>::from(v.as_ref()))),
+            )
+        }
+    }
+
+    pub struct SledTransaction<'a> {
+        db: &'a sled::Db,
+        batch: std::cell::RefCell<sled::Batch>,
+    }
+
+    impl KVStoreRead for SledTransaction<'_> {
+        fn get(&self, col: Col, key: &[u8]) -> Option<bytes::Bytes> {
+            self.db
+                .get(col_key(col, key))
+                .expect("db operation should be ok")
+                .map(|v| bytes::Bytes::copy_from_slice(v.as_ref()))
+        }
+    }
+
+    impl KVStoreWrite for SledTransaction<'_> {
+        fn insert_raw(&self, col: Col, key: &[u8], value: impl Into<bytes::Bytes>) -> Result<(), Error> {
+            let value = value.into();
+            self.batch.borrow_mut().insert(col_key(col, key), value.as_ref());
+            Ok(())
+        }
+
+        fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
+            self.batch.borrow_mut().remove(col_key(col, key));
+            Ok(())
+        }
+    }
+
+    impl KVStore for SledTransaction<'_> {}
+
+    impl SledTransaction<'_> {
+        pub fn commit(self) -> Result<(), Error> {
+            self.db
+                .apply_batch(self.batch.into_inner())
+                .map_err(|e| Error::Store(e.to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "lmdb-backend")]
+pub mod lmdb_backend {
+    use super::*;
+    use heed::types::Bytes;
+    use heed::{Database, Env};
+
+    /// A `KVStore` backed by LMDB via [`heed`].
+    pub struct LmdbStore {
+        env: Env,
+        db: Database<Bytes, Bytes>,
+    }
+
+    impl LmdbStore {
+        pub fn open(env: Env, db: Database<Bytes, Bytes>) -> Self {
+            LmdbStore { env, db }
+        }
+
+        pub fn begin_transaction(&self) -> Result<LmdbTransaction<'_>, Error> {
+            let txn = self
+                .env
+                .write_txn()
+                .map_err(|e| Error::Store(e.to_string()))?;
+            Ok(LmdbTransaction {
+                db: self.db,
+                txn: std::cell::RefCell::new(txn),
+            })
+        }
+    }
+
+    impl KVStoreRead for LmdbStore {
+        fn get(&self, col: Col, key: &[u8]) -> Option<bytes::Bytes> {
+            let txn = self.env.read_txn().expect("db operation should be ok");
+            self.db
+                .get(&txn, &col_key(col, key))
+                .expect("db operation should be ok")
+                .map(|v| bytes::Bytes::copy_from_slice(v.as_ref()))
+        }
+    }
+
+    pub struct LmdbTransaction<'a> {
+        db: Database<Bytes, Bytes>,
+        txn: std::cell::RefCell<heed::RwTxn<'a>>,
+    }
+
+    impl KVStoreRead for LmdbTransaction<'_> {
+        fn get(&self, col: Col, key: &[u8]) -> Option<bytes::Bytes> {
+            self.db
+                .get(&self.txn.borrow(), &col_key(col, key))
+                .expect("db operation should be ok")
+                .map(|v| bytes::Bytes::copy_from_slice(v.as_ref()))
+        }
+    }
+
+    impl KVStoreWrite for LmdbTransaction<'_> {
+        fn insert_raw(&self, col: Col, key: &[u8], value: impl Into<bytes::Bytes>) -> Result<(), Error> {
+            let value = value.into();
+            self.db
+                .put(&mut self.txn.borrow_mut(), &col_key(col, key), value.as_ref())
+                .map_err(|e| Error::Store(e.to_string()))
+        }
+
+        fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
+            self.db
+                .delete(&mut self.txn.borrow_mut(), &col_key(col, key))
+                .map(|_| ())
+                .map_err(|e| Error::Store(e.to_string()))
+        }
+    }
+
+    impl KVStore for LmdbTransaction<'_> {}
+
+    impl LmdbTransaction<'_> {
+        pub fn commit(self) -> Result<(), Error> {
+            self.txn
+                .into_inner()
+                .commit()
+                .map_err(|e| Error::Store(e.to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "sqlite-backend")]
+pub mod sqlite_backend {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A `KVStore` backed by SQLite (single `kv(col, key, value)` table).
+    pub struct SqliteStore {
+        conn: Mutex<rusqlite::Connection>,
+    }
+
+    impl SqliteStore {
+        pub fn open(conn: rusqlite::Connection) -> Result<Self, Error> {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS kv (col INTEGER, key BLOB, value BLOB, PRIMARY KEY (col, key))",
+                [],
+            )
+            .map_err(|e| Error::Store(e.to_string()))?;
+            Ok(SqliteStore {
+                conn: Mutex::new(conn),
+            })
+        }
+
+        pub fn begin_transaction(&self) -> SqliteTransaction<'_> {
+            SqliteTransaction {
+                store: self,
+                ops: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl KVStoreRead for SqliteStore {
+        fn get(&self, col: Col, key: &[u8]) -> Option<bytes::Bytes> {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row(
+                "SELECT value FROM kv WHERE col = ?1 AND key = ?2",
+                rusqlite::params![col as i64, key],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .ok()
+            .map(|v| bytes::Bytes::copy_from_slice(v.as_ref()))
+        }
+    }
+
+    impl KVStoreScan for SqliteStore {
+        fn scan_prefix<'a>(
+            &'a self,
+            col: Col,
+            prefix: &[u8],
+        ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+            // SQLite can't hold the lock across a lazy iterator, so materialize
+            // the prefix range in ascending key order and hand back its cursor.
+            let conn = self.conn.lock().unwrap();
+            let upper = prefix_upper_bound(prefix);
+            let rows: Vec<(Box<[u8]>, Box<[u8]>)> = {
+                let mut stmt = conn
+                    .prepare(match &upper {
+                        Some(_) => "SELECT key, value FROM kv WHERE col = ?1 AND key >= ?2 AND key < ?3 ORDER BY key",
+                        None => "SELECT key, value FROM kv WHERE col = ?1 AND key >= ?2 ORDER BY key",
+                    })
+                    .expect("db operation should be ok");
+                let map = |row: &rusqlite::Row| {
+                    Ok((
+                        Box::<[u8]>::from(row.get::<_, Vec<u8>>(0)?.as_slice()),
+                        Box::<[u8]>::from(row.get::<_, Vec<u8>>(1)?.as_slice()),
+                    ))
+                };
+                let iter = match &upper {
+                    Some(upper) => stmt.query_map(rusqlite::params![col as i64, prefix, upper], map),
+                    None => stmt.query_map(rusqlite::params![col as i64, prefix], map),
+                };
+                iter.expect("db operation should be ok")
+                    .filter_map(|r| r.ok())
+                    .collect()
+            };
+            Box::new(rows.into_iter())
+        }
+    }
+
+    enum Op {
+        Put(Col, Vec<u8>, Vec<u8>),
+        Delete(Col, Vec<u8>),
+    }
+
+    pub struct SqliteTransaction<'a> {
+        store: &'a SqliteStore,
+        ops: Mutex<Vec<Op>>,
+    }
+
+    impl KVStoreRead for SqliteTransaction<'_> {
+        fn get(&self, col: Col, key: &[u8]) -> Option<bytes::Bytes> {
+            self.store.get(col, key)
+        }
+    }
+
+    impl KVStoreWrite for SqliteTransaction<'_> {
+        fn insert_raw(&self, col: Col, key: &[u8], value: impl Into<bytes::Bytes>) -> Result<(), Error> {
+            self.ops
+                .lock()
+                .unwrap()
+                .push(Op::Put(col, key.to_vec(), value.into().to_vec()));
+            Ok(())
+        }
+
+        fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
+            self.ops.lock().unwrap().push(Op::Delete(col, key.to_vec()));
+            Ok(())
+        }
+    }
+
+    impl KVStore for SqliteTransaction<'_> {}
+
+    impl SqliteTransaction<'_> {
+        pub fn commit(self) -> Result<(), Error> {
+            let mut conn = self.store.conn.lock().unwrap();
+            let tx = conn.transaction().map_err(|e| Error::Store(e.to_string()))?;
+            for op in self.ops.into_inner().unwrap() {
+                match op {
+                    Op::Put(col, key, value) => tx.execute(
+                        "INSERT OR REPLACE INTO kv (col, key, value) VALUES (?1, ?2, ?3)",
+                        rusqlite::params![col as i64, key, value],
+                    ),
+                    Op::Delete(col, key) => tx.execute(
+                        "DELETE FROM kv WHERE col = ?1 AND key = ?2",
+                        rusqlite::params![col as i64, key],
+                    ),
+                }
+                .map_err(|e| Error::Store(e.to_string()))?;
+            }
+            tx.commit().map_err(|e| Error::Store(e.to_string()))
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! RocksDB-to-RocksDB SMT migration tool.
+//!
+//! Dumps a tree's leaf and branch column families into a portable,
+//! length-prefixed and CRC32-checksummed stream whose header carries the
+//! source root, then re-imports that stream into a fresh RocksDB instance and
+//! verifies the reconstructed root matches it. Leaves are written in tree
+//! order (via `smt_sort_unstable_kv`) so the target backend sees keys in the
+//! order bulk insertion prefers. Records are not streamed: `dump` collects both
+//! column families in memory, and `import`/`verify` rebuild the whole tree in an
+//! in-memory `DefaultStore`, so the tree must fit in RAM.
+//!
+//! ```text
+//! smt-migrate dump   <src-rocksdb>  <dump-file>
+//! smt-migrate import <dump-file>    <dst-rocksdb>
+//! smt-migrate verify <dump-file>
+//! ```
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process;
+
+use sparse_merkle_tree::{
+    blake2b::Blake2bHasher,
+    default_store::DefaultStore,
+    traits::Store,
+    tree::{BranchKey, BranchNode},
+    SparseMerkleTree, H256,
+};
+
+use smt_rocksdb::db::{Col, Store as RocksStore};
+use smt_rocksdb::smt::serde::{
+    branch_key_to_vec, branch_node_to_vec, slice_to_branch_key, slice_to_branch_node,
+};
+
+const LEAF_COL: Col = 0;
+const BRANCH_COL: Col = 1;
+
+/// Magic header so a truncated or foreign file is rejected early.
+const MAGIC: &[u8; 8] = b"SMTDUMP1";
+/// Record tags inside the stream.
+const TAG_LEAF: u8 = 0;
+const TAG_BRANCH: u8 = 1;
+
+type SMT = SparseMerkleTree<Blake2bHasher, H256, DefaultStore<H256>>;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let code = match args.get(1).map(String::as_str) {
+        Some("dump") => run_dump(&args[2..]),
+        Some("import") => run_import(&args[2..]),
+        Some("verify") => run_verify(&args[2..]),
+        _ => {
+            eprintln!("usage: smt-migrate <dump|import|verify> ...");
+            1
+        }
+    };
+    process::exit(code);
+}
+
+/// Stream every record from a RocksDB-backed tree into a checksummed dump.
+fn run_dump(args: &[String]) -> i32 {
+    let (src, out) = match (args.first(), args.get(1)) {
+        (Some(src), Some(out)) => (src, out),
+        _ => {
+            eprintln!("usage: smt-migrate dump <src-rocksdb> <dump-file>");
+            return 1;
+        }
+    };
+    if let Err(err) = dump(src, out) {
+        eprintln!("dump failed: {err}");
+        return 1;
+    }
+    0
+}
+
+fn dump(src: &str, out: &str) -> io::Result<()> {
+    let db = RocksStore::open_read_only(src, 2).map_err(to_io)?;
+
+    // Leaves first, in tree order, then branches.
+    let mut leaves: Vec<(H256, H256)> =
+        db.scan(LEAF_COL).map(|(k, v)| (decode_h256(&k), decode_h256(&v))).collect();
+    sparse_merkle_tree::h256::smt_sort_unstable_kv(&mut leaves);
+    let branches: Vec<(Vec<u8>, Vec<u8>)> = db.scan(BRANCH_COL).collect();
+    let root = source_root(&leaves, &branches)?;
+
+    let file = File::create(out)?;
+    let mut w = Writer::new(BufWriter::new(file));
+    w.header(&root)?;
+    for (key, value) in &leaves {
+        w.record(TAG_LEAF, key.as_slice(), value.as_slice())?;
+    }
+    for (k, v) in &branches {
+        w.record(TAG_BRANCH, k, v)?;
+    }
+    w.finish()
+}
+
+/// Reconstruct the source tree's root from its raw leaf/branch records so the
+/// dump header carries a value the replayed tree can be checked against.
+fn source_root(leaves: &[(H256, H256)], branches: &[(Vec<u8>, Vec<u8>)]) -> io::Result<H256> {
+    let mut store = DefaultStore::<H256>::default();
+    for (key, value) in leaves {
+        store.insert_leaf(*key, *value).map_err(to_io)?;
+    }
+    for (k, v) in branches {
+        let branch_key = slice_to_branch_key(k).map_err(to_io)?;
+        let branch_node = slice_to_branch_node(v);
+        store.insert_branch(branch_key, branch_node).map_err(to_io)?;
+    }
+    Ok(*SMT::new_with_store(store).map_err(to_io)?.root())
+}
+
+/// Replay a dump into a fresh in-memory tree, returning the root recorded in
+/// the dump header alongside the root recomputed from the replayed records.
+fn replay(path: &str) -> io::Result<(H256, H256, SMT)> {
+    let file = File::open(path)?;
+    let mut r = Reader::new(BufReader::new(file));
+    let source_root = r.header()?;
+    let mut store = DefaultStore::<H256>::default();
+    while let Some((tag, key, value)) = r.record()? {
+        match tag {
+            TAG_LEAF => {
+                store
+                    .insert_leaf(decode_h256(&key), decode_h256(&value))
+                    .map_err(to_io)?;
+            }
+            TAG_BRANCH => {
+                let branch_key = slice_to_branch_key(&key).map_err(to_io)?;
+                let branch_node = slice_to_branch_node(&value);
+                store.insert_branch(branch_key, branch_node).map_err(to_io)?;
+            }
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown record tag {other}"),
+                ))
+            }
+        }
+    }
+    // Derive the root from the replayed store (`SparseMerkleTree::new_with_store`) rather than
+    // trusting the header blindly.
+    let tree = SMT::new_with_store(store).map_err(to_io)?;
+    let recomputed_root = *tree.root();
+    Ok((source_root, recomputed_root, tree))
+}
+
+fn run_import(args: &[String]) -> i32 {
+    let (dump_file, dst) = match (args.first(), args.get(1)) {
+        (Some(d), Some(dst)) => (d, dst),
+        _ => {
+            eprintln!("usage: smt-migrate import <dump-file> <dst-rocksdb>");
+            return 1;
+        }
+    };
+    let (source_root, recomputed_root, tree) = match replay(dump_file) {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("import failed: {err}");
+            return 1;
+        }
+    };
+    if recomputed_root != source_root {
+        eprintln!(
+            "import failed: recomputed root {recomputed_root:?} does not match source root {source_root:?}"
+        );
+        return 1;
+    }
+    if let Err(err) = write_out(dst, &tree) {
+        eprintln!("import failed: {err}");
+        return 1;
+    }
+    println!("imported tree with root {recomputed_root:?} into {dst}");
+    0
+}
+
+/// Persist the reconstructed tree into a fresh RocksDB instance at `dst`.
+fn write_out(dst: &str, tree: &SMT) -> io::Result<()> {
+    let db = RocksStore::open(dst, 2).map_err(to_io)?;
+    let txn = db.begin_transaction();
+    for (key, value) in tree.store().leaves_map() {
+        txn.insert_raw(LEAF_COL, key.as_slice(), value.as_slice())
+            .map_err(to_io)?;
+    }
+    for (branch_key, branch_node) in tree.store().branches_map() {
+        txn.insert_raw(
+            BRANCH_COL,
+            &branch_key_to_vec(branch_key),
+            &branch_node_to_vec(branch_node),
+        )
+        .map_err(to_io)?;
+    }
+    txn.commit().map_err(to_io)
+}
+
+fn run_verify(args: &[String]) -> i32 {
+    let dump_file = match args.first() {
+        Some(d) => d,
+        None => {
+            eprintln!("usage: smt-migrate verify <dump-file>");
+            return 1;
+        }
+    };
+    match replay(dump_file) {
+        Ok((source_root, recomputed_root, _)) => {
+            if recomputed_root != source_root {
+                eprintln!(
+                    "verify failed: recomputed root {recomputed_root:?} does not match source root {source_root:?}"
+                );
+                return 1;
+            }
+            println!("root {recomputed_root:?} matches source");
+            0
+        }
+        Err(err) => {
+            eprintln!("verify failed: {err}");
+            1
+        }
+    }
+}
+
+/// A CRC32-checksummed record writer.
+struct Writer<W: Write> {
+    inner: W,
+    crc: u32,
+}
+
+impl<W: Write> Writer<W> {
+    fn new(inner: W) -> Self {
+        Writer { inner, crc: 0 }
+    }
+
+    fn header(&mut self, root: &H256) -> io::Result<()> {
+        self.write_all(MAGIC)?;
+        self.write_all(root.as_slice())
+    }
+
+    fn record(&mut self, tag: u8, key: &[u8], value: &[u8]) -> io::Result<()> {
+        self.write_all(&[tag])?;
+        self.write_all(&(key.len() as u32).to_le_bytes())?;
+        self.write_all(key)?;
+        self.write_all(&(value.len() as u32).to_le_bytes())?;
+        self.write_all(value)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.crc = crc32(self.crc, buf);
+        self.inner.write_all(buf)
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        let crc = self.crc.to_le_bytes();
+        self.inner.write_all(&crc)?;
+        self.inner.flush()
+    }
+}
+
+/// A CRC32-verifying record reader.
+struct Reader<R: Read> {
+    inner: R,
+    crc: u32,
+}
+
+impl<R: Read> Reader<R> {
+    fn new(inner: R) -> Self {
+        Reader { inner, crc: 0 }
+    }
+
+    fn header(&mut self) -> io::Result<H256> {
+        let mut magic = [0u8; 8];
+        self.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad dump magic"));
+        }
+        let mut root = [0u8; 32];
+        self.read_exact(&mut root)?;
+        Ok(root.into())
+    }
+
+    fn record(&mut self) -> io::Result<Option<(u8, Vec<u8>, Vec<u8>)>> {
+        let mut tag = [0u8; 1];
+        if self.read_trailer_or(&mut tag)? {
+            return Ok(None);
+        }
+        let key = self.read_chunk()?;
+        let value = self.read_chunk()?;
+        Ok(Some((tag[0], key, value)))
+    }
+
+    /// Read a single byte, or — if the 4-byte trailing checksum begins — verify
+    /// it and report end-of-stream.
+    fn read_trailer_or(&mut self, tag: &mut [u8; 1]) -> io::Result<bool> {
+        let mut first = [0u8; 1];
+        if self.inner.read(&mut first)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "missing checksum"));
+        }
+        // A record tag is 0 or 1; anything else means we are at the checksum.
+        // To keep the format simple the trailing checksum is read explicitly
+        // once a peeked tag is out of range.
+        if first[0] == TAG_LEAF || first[0] == TAG_BRANCH {
+            self.crc = crc32(self.crc, &first);
+            tag[0] = first[0];
+            Ok(false)
+        } else {
+            let mut rest = [0u8; 3];
+            self.inner.read_exact(&mut rest)?;
+            let mut stored = [first[0], rest[0], rest[1], rest[2]];
+            let stored = u32::from_le_bytes(std::mem::take(&mut stored));
+            if stored != self.crc {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "checksum mismatch"));
+            }
+            Ok(true)
+        }
+    }
+
+    fn read_chunk(&mut self) -> io::Result<Vec<u8>> {
+        let mut len = [0u8; 4];
+        self.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        let mut buf = vec![0u8; len];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.crc = crc32(self.crc, buf);
+        Ok(())
+    }
+}
+
+fn decode_h256(bytes: &[u8]) -> H256 {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(bytes);
+    out.into()
+}
+
+fn to_io<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+/// A small table-less CRC32 (IEEE) so the tool carries no extra dependency.
+fn crc32(mut crc: u32, buf: &[u8]) -> u32 {
+    crc = !crc;
+    for &byte in buf {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,85 @@
+//! Parameterize a persisted tree over any RustCrypto [`digest::Digest`].
+//!
+//! The SMT core is generic over [`Hasher`], but a persisted tree must also
+//! record *which* hash produced it: opening a store with the wrong digest would
+//! otherwise silently decode mismatched roots. [`DigestHasher`] adapts any
+//! `digest::Digest` (sha2, sha3, blake2, …) into the crate's `Hasher`, and
+//! [`node_header`] builds a 2-byte header — a digest
+//! [`MARKER`](DigestId::MARKER) plus the output width derived from
+//! `D::output_size()` — that [`check_node_header`] validates, rejecting a blob
+//! written under a different digest. The stores and node serializers in this
+//! crate do not add or check this header themselves.
+
+use digest::Digest;
+
+use sparse_merkle_tree::{error::Error as SMTError, traits::Hasher, H256};
+
+/// A RustCrypto digest that carries a stable 1-byte on-disk marker.
+pub trait DigestId: Digest {
+    /// The marker byte persisted in node headers to identify this digest.
+    const MARKER: u8;
+}
+
+#[cfg(feature = "sha2")]
+impl DigestId for sha2::Sha256 {
+    const MARKER: u8 = 1;
+}
+
+#[cfg(feature = "sha3")]
+impl DigestId for sha3::Sha3_256 {
+    const MARKER: u8 = 2;
+}
+
+#[cfg(feature = "blake2")]
+impl DigestId for blake2::Blake2b512 {
+    const MARKER: u8 = 3;
+}
+
+/// Adapts a `digest::Digest` into the crate's [`Hasher`] trait.
+///
+/// Output wider than 32 bytes is truncated and narrower output is
+/// zero-extended, so every digest folds into the fixed-width `H256` the tree
+/// stores while the persisted header still records the true output width.
+pub struct DigestHasher<D: Digest>(D);
+
+impl<D: Digest> Default for DigestHasher<D> {
+    fn default() -> Self {
+        DigestHasher(D::new())
+    }
+}
+
+impl<D: Digest> Hasher for DigestHasher<D> {
+    fn write_h256(&mut self, h: &H256) {
+        self.0.update(h.as_slice());
+    }
+
+    fn write_byte(&mut self, b: u8) {
+        self.0.update([b]);
+    }
+
+    fn finish(self) -> H256 {
+        let out = self.0.finalize();
+        let mut buf = [0u8; 32];
+        let n = core::cmp::min(32, out.len());
+        buf[..n].copy_from_slice(&out[..n]);
+        buf.into()
+    }
+}
+
+/// The 2-byte header stamped on every node a digest persists: `marker || width`.
+pub fn node_header<D: DigestId>() -> [u8; 2] {
+    [D::MARKER, <D as Digest>::output_size() as u8]
+}
+
+/// Validate a persisted node header against `D` and return the node body.
+///
+/// Rejects a blob whose marker or width does not match `D`, so a store written
+/// under one digest cannot be read back under another.
+pub fn check_node_header<D: DigestId>(bytes: &[u8]) -> Result<&[u8], SMTError> {
+    let header = node_header::<D>();
+    match bytes.get(..2) {
+        Some(prefix) if prefix == header => Ok(&bytes[2..]),
+        Some(_) => Err(SMTError::Store("node digest header mismatch".to_string())),
+        None => Err(SMTError::Store("truncated node header".to_string())),
+    }
+}
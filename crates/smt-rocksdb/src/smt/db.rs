@@ -1,10 +1,11 @@
 #![allow(clippy::mutable_key_type)]
 
+use bytes::Bytes;
 use rocksdb::DBPinnableSlice;
 
 use crate::db::Col;
 use crate::traits::kv_store::KVStoreRead;
-use crate::traits::kv_store::{KVStore, KVStoreWrite};
+use crate::traits::kv_store::{KVStore, KVStoreBatch, KVStoreWrite};
 use crate::{error::Error, iter::DBIter, DBIterator, IteratorMode, RocksDBTransaction};
 use crate::{RocksDB, RocksDBSnapshot};
 
@@ -18,6 +19,11 @@ impl<'a> Store {
         Store { db }
     }
 
+    /// Borrow the underlying RocksDB handle, e.g. to create a checkpoint.
+    pub fn inner_db(&self) -> &RocksDB {
+        &self.db
+    }
+
     pub fn open_tmp(columns: u32) -> Self {
         let db = RocksDB::open_tmp(columns);
         Self::new(db)
@@ -41,8 +47,8 @@ impl<'a> Store {
 }
 
 impl KVStoreRead for Store {
-    fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
-        self.get(col, key).map(|v| Box::<[u8]>::from(v.as_ref()))
+    fn get(&self, col: Col, key: &[u8]) -> Option<Bytes> {
+        self.get(col, key).map(|v| Bytes::copy_from_slice(v.as_ref()))
     }
 }
 
@@ -51,17 +57,17 @@ pub struct StoreTransaction {
 }
 
 impl KVStoreRead for StoreTransaction {
-    fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
+    fn get(&self, col: Col, key: &[u8]) -> Option<Bytes> {
         self.inner
             .get(col, key)
             .expect("db operation should be ok")
-            .map(|v| Box::<[u8]>::from(v.as_ref()))
+            .map(|v| Bytes::copy_from_slice(v.as_ref()))
     }
 }
 
 impl KVStoreWrite for StoreTransaction {
-    fn insert_raw(&self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error> {
-        self.inner.put(col, key, value)
+    fn insert_raw(&self, col: Col, key: &[u8], value: impl Into<Bytes>) -> Result<(), Error> {
+        self.inner.put(col, key, value.into().as_ref())
     }
 
     fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
@@ -71,6 +77,10 @@ impl KVStoreWrite for StoreTransaction {
 
 impl KVStore for StoreTransaction {}
 
+// The transaction buffers every op and flushes on `commit`, so replaying a
+// batch through `insert_raw`/`delete` already lands atomically.
+impl KVStoreBatch for StoreTransaction {}
+
 impl StoreTransaction {
     pub fn commit(&self) -> Result<(), Error> {
         self.inner.commit()
@@ -98,10 +108,10 @@ impl StoreSnapshot {
 }
 
 impl KVStoreRead for StoreSnapshot {
-    fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
+    fn get(&self, col: Col, key: &[u8]) -> Option<Bytes> {
         self.inner
             .get_pinned(col, key)
             .expect("db operation should be ok")
-            .map(|v| Box::<[u8]>::from(v.as_ref()))
+            .map(|v| Bytes::copy_from_slice(v.as_ref()))
     }
 }
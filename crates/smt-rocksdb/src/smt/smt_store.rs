@@ -1,5 +1,6 @@
 //! Implement SMTStore trait
 
+use std::borrow::Cow;
 use std::convert::TryInto;
 
 use crate::db::Col;
@@ -34,21 +35,22 @@ impl<'a, DB: KVStore> SMTStore<'a, DB> {
 }
 
 impl<'a, DB: KVStore> Store<H256> for SMTStore<'a, DB> {
-    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, SMTError> {
+    // A deserializing backend must decode the bytes, so it hands back owned values.
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>, SMTError> {
         match self
             .store
             .get(self.branch_col, &branch_key_to_vec(branch_key))
         {
-            Some(slice) => Ok(Some(slice_to_branch_node(&slice))),
+            Some(slice) => Ok(Some(Cow::Owned(slice_to_branch_node(&slice)))),
             None => Ok(None),
         }
     }
 
-    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<H256>, SMTError> {
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<Cow<'_, H256>>, SMTError> {
         match self.store.get(self.leaf_col, leaf_key.as_slice()) {
             Some(slice) if 32 == slice.len() => {
                 let leaf: [u8; 32] = slice.as_ref().try_into().unwrap();
-                Ok(Some(H256::from(leaf)))
+                Ok(Some(Cow::Owned(H256::from(leaf))))
             }
             Some(_) => Err(SMTError::Store("get corrupted leaf".to_string())),
             None => Ok(None),
@@ -60,7 +62,7 @@ impl<'a, DB: KVStore> Store<H256> for SMTStore<'a, DB> {
             .insert_raw(
                 self.branch_col,
                 &branch_key_to_vec(&branch_key),
-                &branch_node_to_vec(&branch),
+                branch_node_to_vec(&branch),
             )
             .map_err(|err| SMTError::Store(format!("insert error {}", err)))?;
 
@@ -69,7 +71,7 @@ impl<'a, DB: KVStore> Store<H256> for SMTStore<'a, DB> {
 
     fn insert_leaf(&mut self, leaf_key: H256, leaf: H256) -> Result<(), SMTError> {
         self.store
-            .insert_raw(self.leaf_col, leaf_key.as_slice(), leaf.as_slice())
+            .insert_raw(self.leaf_col, leaf_key.as_slice(), leaf.as_slice().to_vec())
             .map_err(|err| SMTError::Store(format!("insert error {}", err)))?;
 
         Ok(())
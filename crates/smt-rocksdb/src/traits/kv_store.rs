@@ -1,12 +1,106 @@
 use crate::db::Col;
 use crate::error::Error;
+use bytes::Bytes;
+
 pub trait KVStoreRead {
-    fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>>;
+    /// Fetch a value as reference-counted [`Bytes`].
+    ///
+    /// Returning `Bytes` lets a backend hand out a cheap `clone`/`slice` of a
+    /// buffer it already owns (an mmap'd file, an in-memory map) instead of
+    /// allocating and copying a `Box<[u8]>` on every node fetch, so repeated
+    /// reads of the same node during a multi-key proof walk cost only a
+    /// ref-count bump.
+    fn get(&self, col: Col, key: &[u8]) -> Option<Bytes>;
 }
 
 pub trait KVStoreWrite {
-    fn insert_raw(&self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error>;
+    fn insert_raw(&self, col: Col, key: &[u8], value: impl Into<Bytes>) -> Result<(), Error>;
     fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error>;
 }
 
 pub trait KVStore: KVStoreRead + KVStoreWrite {}
+
+/// A single mutation buffered in a [`WriteBatch`].
+pub enum BatchOp {
+    Put(Col, Box<[u8]>, Box<[u8]>),
+    Delete(Col, Box<[u8]>),
+}
+
+/// An ordered set of mutations committed in one transaction.
+///
+/// A tree `update`/`update_all` touches many branch and leaf nodes; collecting
+/// them into a single `WriteBatch` lets a backend flush them atomically (mapping
+/// directly onto a native RocksDB `WriteBatch`) instead of issuing one call per
+/// node with no crash-atomicity guarantee.
+#[derive(Default)]
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    /// An empty batch.
+    pub fn new() -> Self {
+        WriteBatch { ops: Vec::new() }
+    }
+
+    /// Queue a key/value write into `col`.
+    pub fn put(&mut self, col: Col, key: &[u8], value: &[u8]) {
+        self.ops.push(BatchOp::Put(col, key.into(), value.into()));
+    }
+
+    /// Queue a deletion from `col`.
+    pub fn delete(&mut self, col: Col, key: &[u8]) {
+        self.ops.push(BatchOp::Delete(col, key.into()));
+    }
+
+    /// Number of queued operations.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether the batch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Consume the batch into its operations, in insertion order.
+    pub fn into_ops(self) -> Vec<BatchOp> {
+        self.ops
+    }
+}
+
+/// Ordered iteration over a column on top of [`KVStoreRead`].
+///
+/// Without iteration there is no way to enumerate the leaves or branches in a
+/// column, which blocks full-tree export, garbage collection of orphaned nodes
+/// and subtree reconstruction for migration. Implementations yield entries in
+/// ascending lexicographic order of the key bytes within the column — the sled
+/// backend from its ordered key space and the SQLite backend from an `ORDER BY`
+/// range — so callers can rely on a deterministic traversal. The RocksDB
+/// `Store` does not implement this trait.
+pub trait KVStoreScan: KVStoreRead {
+    /// Iterate every `(key, value)` in `col` whose key starts with `prefix`,
+    /// in ascending key order.
+    fn scan_prefix<'a>(
+        &'a self,
+        col: Col,
+        prefix: &[u8],
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
+}
+
+/// Atomic multi-key writes on top of [`KVStoreWrite`].
+///
+/// The default implementation replays the batch through `insert_raw`/`delete`,
+/// which is already atomic for transaction stores that buffer until `commit`.
+/// Backends with a native batch type (RocksDB) should override `write_batch`.
+pub trait KVStoreBatch: KVStoreWrite {
+    fn write_batch(&self, batch: WriteBatch) -> Result<(), Error> {
+        for op in batch.into_ops() {
+            match op {
+                BatchOp::Put(col, key, value) => self.insert_raw(col, &key, value.into_vec())?,
+                BatchOp::Delete(col, key) => self.delete(col, &key)?,
+            }
+        }
+        Ok(())
+    }
+}
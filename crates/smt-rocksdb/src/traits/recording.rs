@@ -0,0 +1,110 @@
+//! Read-recording and replay wrappers for stateless proof generation.
+//!
+//! [`RecordingKVStore`] forwards every `KVStore` call to an inner store while
+//! remembering each key it reads that holds a value, per [`Col`]; reads that
+//! find no value are not recorded. After a batch of tree
+//! operations, [`into_proof`](RecordingKVStore::into_proof) yields the minimal
+//! `(Col, key, value)` set those operations touched. Replayed into a
+//! [`ReplayKVStore`], that set re-executes the same operations against a fresh
+//! in-memory map — with no access to the full backing database — so a root
+//! transition can be re-verified statelessly, as light-client/ZK provers do.
+//! [`ReplayKVStore::try_get`] errors on any key outside the recorded set, while
+//! the replay store's `KVStoreRead::get` returns `None` for such a key.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use crate::db::Col;
+use crate::error::Error;
+use bytes::Bytes;
+use crate::traits::kv_store::{KVStore, KVStoreRead, KVStoreWrite};
+
+/// A single recorded read: the column, key and the value returned.
+pub type Record = (Col, Box<[u8]>, Bytes);
+
+/// Wraps an inner [`KVStore`], recording every key read for later replay.
+pub struct RecordingKVStore<S> {
+    inner: S,
+    reads: RefCell<BTreeMap<(Col, Box<[u8]>), Bytes>>,
+}
+
+impl<S> RecordingKVStore<S> {
+    /// Wrap `inner` with an empty read log.
+    pub fn new(inner: S) -> Self {
+        RecordingKVStore {
+            inner,
+            reads: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// The minimal set of `(Col, key, value)` reads observed so far.
+    pub fn into_proof(self) -> Vec<Record> {
+        self.reads
+            .into_inner()
+            .into_iter()
+            .map(|((col, key), value)| (col, key, value))
+            .collect()
+    }
+}
+
+impl<S: KVStoreRead> KVStoreRead for RecordingKVStore<S> {
+    fn get(&self, col: Col, key: &[u8]) -> Option<Bytes> {
+        let value = self.inner.get(col, key);
+        if let Some(value) = &value {
+            // Record the value handed back; writes are not recorded since they
+            // produce new state rather than depend on existing state.
+            self.reads
+                .borrow_mut()
+                .insert((col, key.into()), value.clone());
+        }
+        value
+    }
+}
+
+impl<S: KVStoreWrite> KVStoreWrite for RecordingKVStore<S> {
+    fn insert_raw(&self, col: Col, key: &[u8], value: impl Into<Bytes>) -> Result<(), Error> {
+        self.inner.insert_raw(col, key, value)
+    }
+
+    fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
+        self.inner.delete(col, key)
+    }
+}
+
+impl<S: KVStore> KVStore for RecordingKVStore<S> {}
+
+/// A read-only store built from a [`RecordingKVStore`] proof.
+///
+/// [`try_get`](ReplayKVStore::try_get) returns an [`Error::Store`] for a key
+/// outside the recorded set; `get` returns `None` for it. Since reads that
+/// found no value are not recorded, a key absent from the original store also
+/// misses here.
+pub struct ReplayKVStore {
+    reads: BTreeMap<(Col, Box<[u8]>), Bytes>,
+}
+
+impl ReplayKVStore {
+    /// Build a replay store from a recorded proof.
+    pub fn new(proof: Vec<Record>) -> Self {
+        let reads = proof
+            .into_iter()
+            .map(|(col, key, value)| ((col, key), value))
+            .collect();
+        ReplayKVStore { reads }
+    }
+
+    /// Like [`KVStoreRead::get`] but surfaces an error when the key was never
+    /// recorded, rather than an indistinguishable `None`.
+    pub fn try_get(&self, col: Col, key: &[u8]) -> Result<Bytes, Error> {
+        self.reads
+            .get(&(col, key.into()))
+            .cloned()
+            .ok_or_else(|| Error::Store(format!("replay miss: col {col} key {key:?}")))
+    }
+}
+
+impl KVStoreRead for ReplayKVStore {
+    fn get(&self, col: Col, key: &[u8]) -> Option<Bytes> {
+        self.reads.get(&(col, key.into())).cloned()
+    }
+}
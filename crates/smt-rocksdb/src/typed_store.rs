@@ -0,0 +1,319 @@
+//! A persistent `Store<V>` with a typed, single-column node layout.
+//!
+//! Unlike [`RocksStore`](crate::rocks_store::RocksStore), which splits branches
+//! and leaves across two column families, this mirrors the `merkletree-rs`
+//! record layout: every node — branch or leaf — is written into one column
+//! under its node key, tagged with a leading byte recording which kind it is
+//! (empty nodes are not written). The current root is persisted under a reserved meta key so a tree can
+//! be reopened across restarts by [`TypedStore::load_root`] without replaying
+//! any updates. A multi-key `update` buffers into a `WriteBatch` flushed
+//! atomically by [`commit`](TypedStore::commit), and [`gc`](TypedStore::gc)
+//! drops every branch record no longer reachable from the saved root.
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use rocksdb::{ColumnFamilyDescriptor, Options, WriteBatch, DB};
+
+use sparse_merkle_tree::{
+    error::Error as SMTError,
+    merge::MergeValue,
+    traits::Store,
+    tree::{BranchKey, BranchNode},
+    H256,
+};
+
+use crate::config::StoreConfig;
+
+const NODES_CF: &str = "nodes";
+
+// Record-kind tags, one per stored value.
+const NODE_EMPTY: u8 = 0;
+const NODE_BRANCH: u8 = 1;
+const NODE_LEAF: u8 = 2;
+
+// Key-space prefixes keeping branch, leaf and meta records disjoint in one column.
+const KEY_BRANCH: u8 = b'B';
+const KEY_LEAF: u8 = b'L';
+const KEY_META: u8 = b'M';
+
+// Reuse the `MergeValue` tags from `rocks_store` so both encoders agree on disk.
+const TAG_VALUE: u8 = 0;
+const TAG_MERGE_WITH_ZERO: u8 = 1;
+
+const ROOT_META_KEY: &[u8] = b"Mroot";
+
+/// A RocksDB-backed store with a typed single-column node layout.
+pub struct TypedStore<V> {
+    db: DB,
+    // Buffered writes flushed atomically by `commit`.
+    batch: WriteBatch,
+    phantom: core::marker::PhantomData<V>,
+}
+
+impl<V> TypedStore<V> {
+    /// Open (creating if absent) a typed store at the configured path.
+    pub fn open(config: &StoreConfig) -> Result<Self, SMTError> {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+
+        let cfs = vec![ColumnFamilyDescriptor::new(NODES_CF, Options::default())];
+        let db = DB::open_cf_descriptors(&db_opts, &config.path, cfs)
+            .map_err(|e| SMTError::Store(e.to_string()))?;
+        Ok(TypedStore {
+            db,
+            batch: WriteBatch::default(),
+            phantom: core::marker::PhantomData,
+        })
+    }
+
+    /// Flush every buffered write in one atomic batch.
+    pub fn commit(&mut self) -> Result<(), SMTError> {
+        let batch = core::mem::take(&mut self.batch);
+        self.db
+            .write(batch)
+            .map_err(|e| SMTError::Store(e.to_string()))
+    }
+
+    /// Persist `root` under the reserved meta key (buffered until `commit`).
+    pub fn save_root(&mut self, root: &H256) -> Result<(), SMTError> {
+        let cf = self.cf()?;
+        let mut value = Vec::with_capacity(1 + 32);
+        value.push(NODE_EMPTY);
+        value.extend_from_slice(root.as_slice());
+        self.batch.put_cf(cf, ROOT_META_KEY, value);
+        Ok(())
+    }
+
+    /// Load the root persisted by the last committed [`save_root`](Self::save_root),
+    /// or the zero root when the store has never been written.
+    pub fn load_root(&self) -> Result<H256, SMTError> {
+        let cf = self.cf()?;
+        match self
+            .db
+            .get_cf(cf, ROOT_META_KEY)
+            .map_err(|e| SMTError::Store(e.to_string()))?
+        {
+            Some(bytes) if bytes.len() == 33 && bytes[0] == NODE_EMPTY => read_h256(&bytes[1..], &mut 0),
+            Some(_) => Err(SMTError::Store("corrupted root record".to_string())),
+            None => Ok(H256::zero()),
+        }
+    }
+
+    /// Drop every branch record unreachable from the saved root.
+    ///
+    /// Starting at the root branch `(max_height, 0)`, the reachable set is walked
+    /// the same way `verify_integrity` descends, and every other branch record is
+    /// deleted in the pending batch. Leaves are left untouched since a key may be
+    /// re-inserted; call `commit` to make the compaction durable.
+    pub fn gc(&mut self, max_height: u8) -> Result<(), SMTError> {
+        let cf = self.cf()?;
+        let mut reachable: std::collections::BTreeSet<Box<[u8]>> = std::collections::BTreeSet::new();
+        if !self.load_root()?.is_zero() {
+            let mut stack = vec![BranchKey::new(max_height, H256::zero())];
+            while let Some(branch_key) = stack.pop() {
+                let encoded = branch_db_key(&branch_key);
+                let node = match self.read_branch(&branch_key)? {
+                    Some(node) => node,
+                    None => continue,
+                };
+                reachable.insert(encoded);
+                if branch_key.height == 0 {
+                    continue;
+                }
+                let child_height = branch_key.height - 1;
+                let left_key = branch_key.node_key;
+                let mut right_key = branch_key.node_key;
+                right_key.set_bit(branch_key.height);
+                for (child_node_key, child_value) in
+                    [(left_key, &node.left), (right_key, &node.right)]
+                {
+                    if !child_value.is_zero() {
+                        stack.push(BranchKey::new(child_height, child_node_key));
+                    }
+                }
+            }
+        }
+
+        let prefix = [KEY_BRANCH];
+        let iter = self.db.prefix_iterator_cf(cf, prefix);
+        let mut stale: Vec<Box<[u8]>> = Vec::new();
+        for item in iter {
+            let (key, _) = item.map_err(|e| SMTError::Store(e.to_string()))?;
+            if key.first() != Some(&KEY_BRANCH) {
+                break;
+            }
+            if !reachable.contains(key.as_ref()) {
+                stale.push(key);
+            }
+        }
+        for key in stale {
+            self.batch.delete_cf(cf, key);
+        }
+        Ok(())
+    }
+
+    fn read_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, SMTError> {
+        let cf = self.cf()?;
+        match self
+            .db
+            .get_cf(cf, branch_db_key(branch_key))
+            .map_err(|e| SMTError::Store(e.to_string()))?
+        {
+            Some(bytes) => Ok(Some(decode_branch(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn cf(&self) -> Result<&rocksdb::ColumnFamily, SMTError> {
+        self.db
+            .cf_handle(NODES_CF)
+            .ok_or_else(|| SMTError::Store(format!("missing column family {NODES_CF}")))
+    }
+}
+
+impl<V> Store<V> for TypedStore<V>
+where
+    V: Clone + Into<Vec<u8>> + for<'a> TryFrom<&'a [u8]>,
+{
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>, SMTError> {
+        Ok(self.read_branch(branch_key)?.map(Cow::Owned))
+    }
+
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<Cow<'_, V>>, SMTError> {
+        let cf = self.cf()?;
+        match self
+            .db
+            .get_cf(cf, leaf_db_key(leaf_key))
+            .map_err(|e| SMTError::Store(e.to_string()))?
+        {
+            Some(bytes) if bytes.first() == Some(&NODE_LEAF) => {
+                let value = V::try_from(&bytes[1..])
+                    .map_err(|_| SMTError::Store("corrupted leaf value".to_string()))?;
+                Ok(Some(Cow::Owned(value)))
+            }
+            Some(_) => Err(SMTError::Store("corrupted leaf record".to_string())),
+            None => Ok(None),
+        }
+    }
+
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<(), SMTError> {
+        let cf = self.cf()?;
+        self.batch
+            .put_cf(cf, branch_db_key(&branch_key), encode_branch(&branch));
+        Ok(())
+    }
+
+    fn insert_leaf(&mut self, leaf_key: H256, leaf: V) -> Result<(), SMTError> {
+        let cf = self.cf()?;
+        let mut value = vec![NODE_LEAF];
+        value.extend_from_slice(&leaf.into());
+        self.batch.put_cf(cf, leaf_db_key(&leaf_key), value);
+        Ok(())
+    }
+
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<(), SMTError> {
+        let cf = self.cf()?;
+        self.batch.delete_cf(cf, branch_db_key(branch_key));
+        Ok(())
+    }
+
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<(), SMTError> {
+        let cf = self.cf()?;
+        self.batch.delete_cf(cf, leaf_db_key(leaf_key));
+        Ok(())
+    }
+}
+
+fn branch_db_key(key: &BranchKey) -> Vec<u8> {
+    let mut out = Vec::with_capacity(2 + 32);
+    out.push(KEY_BRANCH);
+    out.push(key.height);
+    out.extend_from_slice(key.node_key.as_slice());
+    out
+}
+
+fn leaf_db_key(key: &H256) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1 + 32);
+    out.push(KEY_LEAF);
+    out.extend_from_slice(key.as_slice());
+    out
+}
+
+fn merge_value_to_vec(out: &mut Vec<u8>, value: &MergeValue) {
+    match value {
+        MergeValue::Value(v) => {
+            out.push(TAG_VALUE);
+            out.extend_from_slice(v.as_slice());
+        }
+        MergeValue::MergeWithZero {
+            base_node,
+            zero_bits,
+            zero_count,
+            value,
+        } => {
+            out.push(TAG_MERGE_WITH_ZERO);
+            out.extend_from_slice(base_node.as_slice());
+            out.extend_from_slice(zero_bits.as_slice());
+            out.push(*zero_count);
+            out.extend_from_slice(value.as_slice());
+        }
+    }
+}
+
+fn encode_branch(node: &BranchNode) -> Vec<u8> {
+    let mut out = vec![NODE_BRANCH];
+    merge_value_to_vec(&mut out, &node.left);
+    merge_value_to_vec(&mut out, &node.right);
+    out
+}
+
+fn read_h256(bytes: &[u8], offset: &mut usize) -> Result<H256, SMTError> {
+    let end = *offset + 32;
+    if end > bytes.len() {
+        return Err(SMTError::Store("truncated node".to_string()));
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(&bytes[*offset..end]);
+    *offset = end;
+    Ok(buf.into())
+}
+
+fn read_merge_value(bytes: &[u8], offset: &mut usize) -> Result<MergeValue, SMTError> {
+    let tag = *bytes
+        .get(*offset)
+        .ok_or_else(|| SMTError::Store("truncated merge value".to_string()))?;
+    *offset += 1;
+    match tag {
+        TAG_VALUE => Ok(MergeValue::Value(read_h256(bytes, offset)?)),
+        TAG_MERGE_WITH_ZERO => {
+            let base_node = read_h256(bytes, offset)?;
+            let zero_bits = read_h256(bytes, offset)?;
+            let zero_count = *bytes
+                .get(*offset)
+                .ok_or_else(|| SMTError::Store("truncated merge value".to_string()))?;
+            *offset += 1;
+            let value = read_h256(bytes, offset)?;
+            Ok(MergeValue::MergeWithZero {
+                base_node,
+                zero_bits,
+                zero_count,
+                value,
+            })
+        }
+        other => Err(SMTError::Store(format!("invalid merge value tag {other}"))),
+    }
+}
+
+fn decode_branch(bytes: &[u8]) -> Result<BranchNode, SMTError> {
+    match bytes.first() {
+        Some(&NODE_BRANCH) => {
+            let mut offset = 1;
+            let left = read_merge_value(bytes, &mut offset)?;
+            let right = read_merge_value(bytes, &mut offset)?;
+            Ok(BranchNode { left, right })
+        }
+        _ => Err(SMTError::Store("corrupted branch record".to_string())),
+    }
+}
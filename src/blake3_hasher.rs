@@ -0,0 +1,34 @@
+//! A Blake3-backed [`Hasher`], gated behind the `blake3` cargo feature.
+//!
+//! The whole module compiles only when `blake3` is enabled, so the optional
+//! `blake3` dependency is never pulled in for the default Blake2b build.
+#![cfg(feature = "blake3")]
+
+use crate::{default_store::DefaultStore, traits::Hasher, tree::SparseMerkleTree, H256};
+use blake3::Hasher as Blake3;
+
+/// A default-store sparse Merkle tree keyed by [`Blake3Hasher`], mirroring the
+/// `VsSmt` alias so callers can write `Blake3Smt::<H256>::default()` directly.
+pub type Blake3Smt<V> = SparseMerkleTree<Blake3Hasher, V, DefaultStore<V>>;
+
+/// A `Hasher` backed by Blake3, producing the same 32-byte output width as
+/// `CkbBlake2bHasher`. The byte feeding order is identical, so the
+/// `MERGE_NORMAL`/`MERGE_ZEROS` domain separation in `merge()` stays intact and
+/// roots are interoperable with Blake3-based SMT stacks.
+#[derive(Default)]
+pub struct Blake3Hasher(Blake3);
+
+impl Hasher for Blake3Hasher {
+    fn write_h256(&mut self, h: &H256) {
+        self.0.update(h.as_slice());
+    }
+
+    fn write_byte(&mut self, b: u8) {
+        self.0.update(&[b][..]);
+    }
+
+    fn finish(self) -> H256 {
+        let hash: [u8; 32] = self.0.finalize().into();
+        hash.into()
+    }
+}
@@ -0,0 +1,83 @@
+//! A bounded LRU cache wrapping any `Store`.
+//!
+//! Proof generation and repeated `get`s read the same branch nodes over and
+//! over; [`CacheStore`] keeps a bounded LRU of recently-touched branch nodes in
+//! memory (keyed by [`BranchKey`]) and writes through on insert/remove, so it
+//! can be dropped in transparently: `SparseMerkleTree::new` accepts a
+//! `CacheStore<SMTStore<..>>` unchanged.
+
+use core::cell::RefCell;
+use core::num::NonZeroUsize;
+
+use lru::LruCache;
+
+use crate::{
+    borrow::Cow,
+    error::Result,
+    traits::{StoreReadOps, StoreWriteOps},
+    tree::{BranchKey, BranchNode},
+    H256,
+};
+
+/// A write-through LRU branch-node cache over an inner store.
+pub struct CacheStore<S> {
+    inner: S,
+    cache: RefCell<LruCache<BranchKey, BranchNode>>,
+}
+
+impl<S> CacheStore<S> {
+    /// Wrap `inner` with a cache holding at most `capacity` branch nodes.
+    pub fn new(inner: S, capacity: NonZeroUsize) -> Self {
+        CacheStore {
+            inner,
+            cache: RefCell::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Borrow the inner store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap the inner store, dropping the cache.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V>> StoreReadOps<V> for CacheStore<S> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        if let Some(node) = self.cache.borrow_mut().get(key) {
+            return Ok(Some(Cow::Owned(node.clone())));
+        }
+        match self.inner.get_branch(key)? {
+            Some(node) => {
+                let node = node.into_owned();
+                self.cache.borrow_mut().put(key.clone(), node.clone());
+                Ok(Some(Cow::Owned(node)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        self.inner.get_leaf(key)
+    }
+}
+
+impl<V, S: StoreWriteOps<V>> StoreWriteOps<V> for CacheStore<S> {
+    fn insert_branch(&mut self, key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.cache.borrow_mut().put(key.clone(), branch.clone());
+        self.inner.insert_branch(key, branch)
+    }
+    fn insert_leaf(&mut self, key: H256, leaf: V) -> Result<()> {
+        self.inner.insert_leaf(key, leaf)
+    }
+    fn remove_branch(&mut self, key: &BranchKey) -> Result<()> {
+        self.cache.borrow_mut().pop(key);
+        self.inner.remove_branch(key)
+    }
+    fn remove_leaf(&mut self, key: &H256) -> Result<()> {
+        self.inner.remove_leaf(key)
+    }
+}
@@ -0,0 +1,210 @@
+//! Checkpoint and rollback for speculative updates.
+//!
+//! [`CheckpointSmt`] wraps a tree whose store is a [`CheckpointStore`]. While a
+//! checkpoint is open the store records, for every branch/leaf it is about to
+//! overwrite or remove, the prior stored value into a per-checkpoint undo frame;
+//! [`rewind`](CheckpointSmt::rewind) replays those entries in reverse to restore
+//! the store and resets the root to the value saved when the checkpoint was
+//! taken. Checkpoints nest: [`drop_checkpoint`](CheckpointSmt::drop_checkpoint)
+//! merges the top frame into the one below so an outer rewind still undoes the
+//! inner work. This brings eager-prune-plus-rollback to the crate, useful for
+//! block execution that may be reverted.
+
+use crate::{
+    borrow::Cow,
+    error::{Error, Result},
+    traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
+    tree::{BranchKey, BranchNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+
+/// Opaque handle identifying an open checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointId(usize);
+
+/// A single recorded prior value to restore on rewind.
+enum Undo<V> {
+    Branch(BranchKey, Option<BranchNode>),
+    Leaf(H256, Option<V>),
+}
+
+/// A store wrapper that records undo information while checkpoints are open.
+pub struct CheckpointStore<V, S> {
+    inner: S,
+    // One undo frame per open checkpoint, oldest first; entries within a frame
+    // are in chronological write order.
+    frames: Vec<Vec<Undo<V>>>,
+}
+
+impl<V, S> CheckpointStore<V, S> {
+    /// Wrap an inner store with no checkpoints open.
+    pub fn new(inner: S) -> Self {
+        CheckpointStore {
+            inner,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Borrow the inner store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn recording(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    fn push_undo(&mut self, undo: Undo<V>) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.push(undo);
+        }
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V>> CheckpointStore<V, S> {
+    fn record_branch(&mut self, key: &BranchKey) -> Result<()> {
+        if self.recording() {
+            let prior = self.inner.get_branch(key)?.map(Cow::into_owned);
+            self.push_undo(Undo::Branch(key.clone(), prior));
+        }
+        Ok(())
+    }
+
+    fn record_leaf(&mut self, key: &H256) -> Result<()> {
+        if self.recording() {
+            let prior = self.inner.get_leaf(key)?.map(Cow::into_owned);
+            self.push_undo(Undo::Leaf(*key, prior));
+        }
+        Ok(())
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V>> StoreReadOps<V> for CheckpointStore<V, S> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        self.inner.get_branch(key)
+    }
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        self.inner.get_leaf(key)
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V> + StoreWriteOps<V>> StoreWriteOps<V> for CheckpointStore<V, S> {
+    fn insert_branch(&mut self, key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.record_branch(&key)?;
+        self.inner.insert_branch(key, branch)
+    }
+    fn insert_leaf(&mut self, key: H256, leaf: V) -> Result<()> {
+        self.record_leaf(&key)?;
+        self.inner.insert_leaf(key, leaf)
+    }
+    fn remove_branch(&mut self, key: &BranchKey) -> Result<()> {
+        self.record_branch(key)?;
+        self.inner.remove_branch(key)
+    }
+    fn remove_leaf(&mut self, key: &H256) -> Result<()> {
+        self.record_leaf(key)?;
+        self.inner.remove_leaf(key)
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V> + StoreWriteOps<V>> CheckpointStore<V, S> {
+    /// Open a new undo frame and return its depth index.
+    fn open(&mut self) -> usize {
+        self.frames.push(Vec::new());
+        self.frames.len() - 1
+    }
+
+    /// Replay and discard every frame from the top down to `index` inclusive,
+    /// restoring the inner store to the state before `index` was opened.
+    fn rewind_to(&mut self, index: usize) -> Result<()> {
+        while self.frames.len() > index {
+            let frame = self.frames.pop().expect("frame exists");
+            for undo in frame.into_iter().rev() {
+                match undo {
+                    Undo::Branch(key, Some(node)) => self.inner.insert_branch(key, node)?,
+                    Undo::Branch(key, None) => self.inner.remove_branch(&key)?,
+                    Undo::Leaf(key, Some(value)) => self.inner.insert_leaf(key, value)?,
+                    Undo::Leaf(key, None) => self.inner.remove_leaf(&key)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge the top frame into the one below so an outer rewind still undoes it.
+    fn merge_top(&mut self) {
+        if let Some(top) = self.frames.pop() {
+            if let Some(below) = self.frames.last_mut() {
+                below.extend(top);
+            }
+        }
+    }
+}
+
+/// A tree that supports checkpoint/rewind over a [`CheckpointStore`].
+pub struct CheckpointSmt<H, V, S> {
+    // Always `Some` outside the short window where `rewind` rebuilds the tree.
+    tree: Option<SparseMerkleTree<H, V, CheckpointStore<V, S>>>,
+    // Root saved when each open checkpoint was taken, parallel to the store frames.
+    saved_roots: Vec<H256>,
+}
+
+impl<H: Hasher + Default, V: Value + Clone, S: StoreReadOps<V> + StoreWriteOps<V>>
+    CheckpointSmt<H, V, S>
+{
+    /// Wrap a root and inner store.
+    pub fn new(root: H256, store: S) -> Self {
+        CheckpointSmt {
+            tree: Some(SparseMerkleTree::new(root, CheckpointStore::new(store))),
+            saved_roots: Vec::new(),
+        }
+    }
+
+    fn tree(&self) -> &SparseMerkleTree<H, V, CheckpointStore<V, S>> {
+        self.tree.as_ref().expect("tree present")
+    }
+
+    fn tree_mut(&mut self) -> &mut SparseMerkleTree<H, V, CheckpointStore<V, S>> {
+        self.tree.as_mut().expect("tree present")
+    }
+
+    /// Current merkle root.
+    pub fn root(&self) -> &H256 {
+        self.tree().root()
+    }
+
+    /// Update a leaf, recording undo information if a checkpoint is open.
+    pub fn update(&mut self, key: H256, value: V) -> Result<&H256> {
+        self.tree_mut().update(key, value)
+    }
+
+    /// Record the current state and open a change log.
+    pub fn checkpoint(&mut self) -> CheckpointId {
+        let root = *self.root();
+        let index = self.tree_mut().store_mut().open();
+        self.saved_roots.push(root);
+        CheckpointId(index)
+    }
+
+    /// Restore the tree to exactly the state captured by `id`.
+    pub fn rewind(&mut self, id: CheckpointId) -> Result<&H256> {
+        if id.0 >= self.saved_roots.len() {
+            return Err(Error::Store("unknown checkpoint".into()));
+        }
+        let root = self.saved_roots[id.0];
+        let mut tree = self.tree.take().expect("tree present");
+        tree.store_mut().rewind_to(id.0)?;
+        self.saved_roots.truncate(id.0);
+        // Reset the root by rebuilding the tree view around the restored store.
+        self.tree = Some(SparseMerkleTree::new(root, tree.take_store()));
+        Ok(self.tree().root())
+    }
+
+    /// Commit the innermost checkpoint, merging its change log into the enclosing
+    /// one (or discarding it outright at the outermost level).
+    pub fn drop_checkpoint(&mut self) {
+        self.tree_mut().store_mut().merge_top();
+        self.saved_roots.pop();
+    }
+}
@@ -0,0 +1,182 @@
+//! A [`Value`] adaptor letting one leaf stand in for a large byte buffer while
+//! keeping per-piece integrity.
+//!
+//! The buffer is split into fixed `piece_size` pieces (a power of two, e.g.
+//! 256 KiB); each piece is hashed with `H`, and a balanced binary Merkle tree is
+//! built over the piece hashes. [`to_h256`](Value::to_h256) returns that
+//! piece-tree root, so a [`ChunkedBlob`] slots directly into `update` and the
+//! main tree never grows with the blob. A sub-proof ties an individual piece to
+//! the blob root, and rewriting one piece recomputes the root by folding the new
+//! piece hash up that piece's path (O(log pieces) hashes); building that path
+//! first rehashes every piece, since no piece tree is cached. This is the
+//! content-Merkle design used by
+//! encrypted filesystems, applied to make the SMT an authenticated index over
+//! large objects.
+
+use core::marker::PhantomData;
+
+use crate::{
+    traits::{Hasher, Value},
+    vec::Vec,
+    H256,
+};
+
+/// A byte buffer addressed as fixed-size pieces under a piece-Merkle root.
+#[derive(Debug, Clone)]
+pub struct ChunkedBlob<H> {
+    data: Vec<u8>,
+    piece_size: usize,
+    phantom: PhantomData<H>,
+}
+
+/// One step of a piece sub-proof: the sibling hash and whether it is the right
+/// child. `None` marks a level where the node was promoted without a sibling.
+pub type PieceProof = Vec<Option<(bool, H256)>>;
+
+impl<H> ChunkedBlob<H> {
+    /// Wrap `data`, splitting it into `piece_size`-byte pieces.
+    ///
+    /// `piece_size` must be a non-zero power of two.
+    pub fn new(data: Vec<u8>, piece_size: usize) -> Self {
+        debug_assert!(piece_size.is_power_of_two(), "piece_size must be a power of two");
+        ChunkedBlob {
+            data,
+            piece_size,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Borrow the underlying bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Number of pieces the buffer splits into.
+    pub fn piece_count(&self) -> usize {
+        self.data.len().div_ceil(self.piece_size)
+    }
+
+    /// Borrow piece `index`, or `None` if out of range.
+    pub fn piece(&self, index: usize) -> Option<&[u8]> {
+        let start = index.checked_mul(self.piece_size)?;
+        if start >= self.data.len() {
+            return None;
+        }
+        let end = core::cmp::min(start + self.piece_size, self.data.len());
+        Some(&self.data[start..end])
+    }
+}
+
+impl<H: Hasher + Default> ChunkedBlob<H> {
+    /// Hash every piece, leaf order.
+    fn piece_hashes(&self) -> Vec<H256> {
+        (0..self.piece_count())
+            .map(|i| hash_piece::<H>(self.piece(i).unwrap_or(&[])))
+            .collect()
+    }
+
+    /// Build every level of the balanced piece tree, leaves first.
+    fn levels(&self) -> Vec<Vec<H256>> {
+        let mut levels = Vec::new();
+        let mut level = self.piece_hashes();
+        if level.is_empty() {
+            return levels;
+        }
+        levels.push(level.clone());
+        while level.len() > 1 {
+            let mut next = Vec::with_capacity(level.len().div_ceil(2));
+            let mut i = 0;
+            while i < level.len() {
+                if i + 1 < level.len() {
+                    next.push(hash_nodes::<H>(&level[i], &level[i + 1]));
+                } else {
+                    // Odd node out: promote it unchanged.
+                    next.push(level[i]);
+                }
+                i += 2;
+            }
+            levels.push(next.clone());
+            level = next;
+        }
+        levels
+    }
+
+    /// The piece-tree root, i.e. this blob's leaf hash in the main SMT.
+    pub fn root(&self) -> H256 {
+        self.levels().last().map(|top| top[0]).unwrap_or_else(H256::zero)
+    }
+
+    /// Produce a sub-proof tying piece `index` to the blob root.
+    pub fn piece_proof(&self, index: usize) -> Option<PieceProof> {
+        if index >= self.piece_count() {
+            return None;
+        }
+        let levels = self.levels();
+        let mut proof = Vec::with_capacity(levels.len().saturating_sub(1));
+        let mut idx = index;
+        for level in &levels[..levels.len() - 1] {
+            if idx % 2 == 0 {
+                if idx + 1 < level.len() {
+                    proof.push(Some((true, level[idx + 1])));
+                } else {
+                    proof.push(None);
+                }
+            } else {
+                proof.push(Some((false, level[idx - 1])));
+            }
+            idx /= 2;
+        }
+        Some(proof)
+    }
+
+    /// Recompute the blob root after rewriting piece `index` by folding the new
+    /// piece hash up its existing path. The fold is O(log pieces), but the path
+    /// comes from [`piece_proof`](Self::piece_proof), which hashes every piece.
+    pub fn root_after_update(&self, index: usize, new_piece: &[u8]) -> Option<H256> {
+        let proof = self.piece_proof(index)?;
+        Some(fold_piece::<H>(hash_piece::<H>(new_piece), &proof))
+    }
+}
+
+/// Fold a piece hash up to the root using a [`PieceProof`].
+pub fn fold_piece<H: Hasher + Default>(leaf: H256, proof: &PieceProof) -> H256 {
+    let mut acc = leaf;
+    for step in proof {
+        match step {
+            Some((true, sibling)) => acc = hash_nodes::<H>(&acc, sibling),
+            Some((false, sibling)) => acc = hash_nodes::<H>(sibling, &acc),
+            None => {}
+        }
+    }
+    acc
+}
+
+impl<H: Hasher + Default> Value for ChunkedBlob<H> {
+    fn to_h256(&self) -> H256 {
+        self.root()
+    }
+    fn zero() -> Self {
+        ChunkedBlob {
+            data: Vec::new(),
+            piece_size: 1,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Hash an arbitrary byte slice through the crate's byte-oriented [`Hasher`].
+fn hash_piece<H: Hasher + Default>(bytes: &[u8]) -> H256 {
+    let mut hasher = H::default();
+    for &byte in bytes {
+        hasher.write_byte(byte);
+    }
+    hasher.finish()
+}
+
+/// Hash an ordered pair of child node hashes.
+fn hash_nodes<H: Hasher + Default>(left: &H256, right: &H256) -> H256 {
+    let mut hasher = H::default();
+    hasher.write_h256(left);
+    hasher.write_h256(right);
+    hasher.finish()
+}
@@ -0,0 +1,103 @@
+//! Fixed-depth inclusion witnesses for in-circuit verification.
+//!
+//! [`MerklePath`](crate::merkle_path::MerklePath) folds a variable number of
+//! siblings and lets empty subtrees collapse, mirroring the optimized store.
+//! An arithmetic circuit (Poseidon/SNARK) cannot branch on a variable-length
+//! program, so [`CircuitWitness`] instead carries *exactly* one sibling per tree
+//! height, ordered leaf-to-root, together with the bit decomposition of the key.
+//! Every height has a sibling, including through empty subtrees: an empty
+//! sibling is simply the zero sentinel `H256::zero()`, which [`merge`] folds away
+//! exactly as the optimized tree does when a child subtree holds no node. The
+//! bottom-up fold in [`check_inclusion`] therefore reproduces
+//! [`SparseMerkleTree::root`](crate::tree::SparseMerkleTree::root).
+
+use crate::{
+    merge::{merge, MergeValue},
+    traits::Hasher,
+    vec::Vec,
+    H256,
+};
+
+/// The number of tree heights in a full 256-bit key path.
+pub const TREE_HEIGHT: usize = 256;
+
+/// A canonical, circuit-ready opening of a single key.
+///
+/// `auth_path[h]` is the sibling subtree hash at height `h`, with `H256::zero()`
+/// standing in for an empty sibling, and `key_bits[h]` is bit `h` of the key
+/// (`true` when the leaf sits in the right child at that height). Both are
+/// fixed at [`TREE_HEIGHT`] entries so the witness maps directly onto a
+/// fixed-arity circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitWitness {
+    key: H256,
+    leaf_hash: H256,
+    auth_path: Vec<H256>,
+    key_bits: Vec<bool>,
+}
+
+impl CircuitWitness {
+    /// Assemble a witness from its fixed-depth parts.
+    pub fn new(key: H256, leaf_hash: H256, auth_path: Vec<H256>, key_bits: Vec<bool>) -> Self {
+        CircuitWitness {
+            key,
+            leaf_hash,
+            auth_path,
+            key_bits,
+        }
+    }
+
+    /// The key this witness opens.
+    pub fn key(&self) -> &H256 {
+        &self.key
+    }
+
+    /// The leaf hash folded at height 0.
+    pub fn leaf_hash(&self) -> &H256 {
+        &self.leaf_hash
+    }
+
+    /// The sibling hashes, height 0 (leaf) first.
+    pub fn auth_path(&self) -> &Vec<H256> {
+        &self.auth_path
+    }
+
+    /// The key bits, height 0 first; `true` means the leaf is the right child.
+    pub fn key_bits(&self) -> &Vec<bool> {
+        &self.key_bits
+    }
+
+    /// Recompute the root by folding the path bottom-up, hashing the running
+    /// subtree against each sibling according to the key bit at that height.
+    pub fn compute_root<H: Hasher + Default>(&self) -> H256 {
+        check_inclusion::<H>(&self.auth_path, self.key, self.leaf_hash)
+    }
+}
+
+/// Fold `auth_path` from leaf to root and return the recomputed root.
+///
+/// `key` selects left/right at each height and `leaf_hash` is the value folded
+/// at height 0. A zero sibling (`H256::zero()`) contributes an empty subtree,
+/// matching how the optimized tree treats a missing child.
+pub fn check_inclusion<H: Hasher + Default>(
+    auth_path: &[H256],
+    key: H256,
+    leaf_hash: H256,
+) -> H256 {
+    let mut current = MergeValue::from_h256(leaf_hash);
+    for (height, sibling_hash) in auth_path.iter().enumerate() {
+        let height = height as u8;
+        let parent_key = key.parent_path(height);
+        let sibling = if sibling_hash.is_zero() {
+            MergeValue::zero()
+        } else {
+            MergeValue::from_h256(*sibling_hash)
+        };
+        current = if key.is_right(height) {
+            merge::<H>(height, &parent_key, &sibling, &current)
+        } else {
+            merge::<H>(height, &parent_key, &current, &sibling)
+        };
+    }
+    current.hash::<H>()
+}
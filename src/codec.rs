@@ -0,0 +1,205 @@
+//! Canonical, versioned wire format for the crate's node and proof types.
+//!
+//! Backends persist and exchange nodes without reinventing a serializer: every
+//! blob begins with a 1-byte [`VERSION`] tag, hashes are stored as their raw 32
+//! bytes, and multi-byte integers use little-endian. The layout is stable, so a
+//! node written by one backend decodes identically in another.
+
+use crate::{
+    error::{Error, Result},
+    hash_algorithm::IdentifiedHasher,
+    merge::MergeValue,
+    merkle_proof::MerkleProof,
+    tree::{BranchKey, BranchNode},
+    vec::Vec,
+    H256,
+};
+
+/// Wire format version. Bumped on any incompatible layout change.
+pub const VERSION: u8 = 1;
+
+// MergeValue variant tags.
+const TAG_VALUE: u8 = 0;
+const TAG_MERGE_WITH_ZERO: u8 = 1;
+
+/// Encode a `BranchKey` as `height(1) || node_key(32)`.
+pub fn encode_branch_key(key: &BranchKey) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(33);
+    buf.push(key.height);
+    buf.extend_from_slice(key.node_key.as_slice());
+    buf
+}
+
+/// Decode a `BranchKey` from `height(1) || node_key(32)`.
+pub fn decode_branch_key(data: &[u8]) -> Result<BranchKey> {
+    if data.len() != 33 {
+        return Err(Error::CorruptedProof);
+    }
+    let node_key: [u8; 32] = data[1..33].try_into().unwrap();
+    Ok(BranchKey::new(data[0], node_key.into()))
+}
+
+/// Append a `MergeValue` in its tagged form to `buf`.
+fn put_merge_value(buf: &mut Vec<u8>, value: &MergeValue) {
+    match value {
+        MergeValue::Value(v) => {
+            buf.push(TAG_VALUE);
+            buf.extend_from_slice(v.as_slice());
+        }
+        MergeValue::MergeWithZero {
+            base_node,
+            zero_bits,
+            zero_count,
+            value,
+        } => {
+            buf.push(TAG_MERGE_WITH_ZERO);
+            buf.extend_from_slice(base_node.as_slice());
+            buf.extend_from_slice(zero_bits.as_slice());
+            buf.push(*zero_count);
+            buf.extend_from_slice(value.as_slice());
+        }
+    }
+}
+
+/// Read one `MergeValue`, returning it and the number of bytes consumed.
+fn take_merge_value(data: &[u8]) -> Result<(MergeValue, usize)> {
+    match data.first() {
+        Some(&TAG_VALUE) if data.len() >= 33 => {
+            let v: [u8; 32] = data[1..33].try_into().unwrap();
+            Ok((MergeValue::from_h256(v.into()), 33))
+        }
+        Some(&TAG_MERGE_WITH_ZERO) if data.len() >= 98 => {
+            let base_node: [u8; 32] = data[1..33].try_into().unwrap();
+            let zero_bits: [u8; 32] = data[33..65].try_into().unwrap();
+            let zero_count = data[65];
+            let value: [u8; 32] = data[66..98].try_into().unwrap();
+            Ok((
+                MergeValue::MergeWithZero {
+                    base_node: base_node.into(),
+                    zero_bits: zero_bits.into(),
+                    zero_count,
+                    value: value.into(),
+                },
+                98,
+            ))
+        }
+        _ => Err(Error::CorruptedProof),
+    }
+}
+
+/// Encode a `MergeValue` with a leading version byte.
+pub fn encode_merge_value(value: &MergeValue) -> Vec<u8> {
+    let mut buf = crate::vec![VERSION];
+    put_merge_value(&mut buf, value);
+    buf
+}
+
+/// Decode a version-prefixed `MergeValue`.
+pub fn decode_merge_value(data: &[u8]) -> Result<MergeValue> {
+    check_version(data)?;
+    take_merge_value(&data[1..]).map(|(v, _)| v)
+}
+
+/// Encode a `BranchNode` as `version || left || right`.
+pub fn encode_branch_node(node: &BranchNode) -> Vec<u8> {
+    let mut buf = crate::vec![VERSION];
+    put_merge_value(&mut buf, &node.left);
+    put_merge_value(&mut buf, &node.right);
+    buf
+}
+
+/// Decode a version-prefixed `BranchNode`.
+pub fn decode_branch_node(data: &[u8]) -> Result<BranchNode> {
+    check_version(data)?;
+    let (left, consumed) = take_merge_value(&data[1..])?;
+    let (right, _) = take_merge_value(&data[1 + consumed..])?;
+    Ok(BranchNode { left, right })
+}
+
+/// Encode a `MerkleProof` as `version || n || bitmaps || m || path`.
+pub fn encode_merkle_proof(proof: &MerkleProof) -> Vec<u8> {
+    let mut buf = crate::vec![VERSION];
+    let bitmaps = proof.leaves_bitmap();
+    buf.extend_from_slice(&(bitmaps.len() as u32).to_le_bytes());
+    for bitmap in bitmaps {
+        buf.extend_from_slice(bitmap.as_slice());
+    }
+    let path = proof.merkle_path();
+    buf.extend_from_slice(&(path.len() as u32).to_le_bytes());
+    for node in path {
+        put_merge_value(&mut buf, node);
+    }
+    buf
+}
+
+/// Decode a version-prefixed `MerkleProof`.
+pub fn decode_merkle_proof(data: &[u8]) -> Result<MerkleProof> {
+    check_version(data)?;
+    let mut offset = 1;
+    let mut read_u32 = |offset: &mut usize| -> Result<u32> {
+        if *offset + 4 > data.len() {
+            return Err(Error::CorruptedProof);
+        }
+        let n = u32::from_le_bytes(data[*offset..*offset + 4].try_into().unwrap());
+        *offset += 4;
+        Ok(n)
+    };
+
+    // Each bitmap/path entry is at least 32/33 bytes on the wire, so the
+    // remaining buffer caps how large a legitimate count can actually be;
+    // pre-allocating the raw (attacker-controlled) u32 count instead would let
+    // a few header bytes claim a multi-gigabyte capacity before any of the
+    // backing data is even read.
+    let bitmap_count = read_u32(&mut offset)? as usize;
+    let mut leaves_bitmap = Vec::with_capacity(bitmap_count.min((data.len() - offset) / 32));
+    for _ in 0..bitmap_count {
+        if offset + 32 > data.len() {
+            return Err(Error::CorruptedProof);
+        }
+        let bitmap: [u8; 32] = data[offset..offset + 32].try_into().unwrap();
+        leaves_bitmap.push(bitmap.into());
+        offset += 32;
+    }
+
+    let path_count = read_u32(&mut offset)? as usize;
+    let mut merkle_path = Vec::with_capacity(path_count.min((data.len() - offset) / 33));
+    for _ in 0..path_count {
+        let (value, consumed) = take_merge_value(&data[offset..])?;
+        merkle_path.push(value);
+        offset += consumed;
+    }
+
+    Ok(MerkleProof::new(leaves_bitmap, merkle_path))
+}
+
+/// Encode a `MerkleProof` tagged with the producing hasher's algorithm id.
+///
+/// The single algorithm byte follows the [`VERSION`] tag, so a proof built with
+/// one hash backend can be identified on the wire and rejected by a decoder
+/// expecting another.
+pub fn encode_merkle_proof_with_algorithm<H: IdentifiedHasher>(proof: &MerkleProof) -> Vec<u8> {
+    let mut buf = crate::vec![VERSION, H::ALGORITHM.id()];
+    buf.extend_from_slice(&encode_merkle_proof(proof)[1..]);
+    buf
+}
+
+/// Decode a proof, rejecting one whose header algorithm id is not `H`'s.
+pub fn decode_merkle_proof_with_algorithm<H: IdentifiedHasher>(data: &[u8]) -> Result<MerkleProof> {
+    check_version(data)?;
+    match data.get(1) {
+        Some(&id) if id == H::ALGORITHM.id() => {}
+        Some(&id) => return Err(Error::InvalidCode(id)),
+        None => return Err(Error::CorruptedProof),
+    }
+    // Splice the version byte back on so the base decoder sees its expected prefix.
+    let mut body = crate::vec![VERSION];
+    body.extend_from_slice(&data[2..]);
+    decode_merkle_proof(&body)
+}
+
+fn check_version(data: &[u8]) -> Result<()> {
+    match data.first() {
+        Some(&VERSION) => Ok(()),
+        _ => Err(Error::CorruptedProof),
+    }
+}
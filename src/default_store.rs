@@ -1,4 +1,5 @@
 use crate::{
+    borrow::Cow,
     collections,
     error::Error,
     traits::{StoreReadOps, StoreWriteOps},
@@ -26,11 +27,12 @@ impl<V> DefaultStore<V> {
 }
 
 impl<V: Clone> StoreReadOps<V> for DefaultStore<V> {
-    fn get_branch(&self, key: &H256) -> Result<Option<BranchNode>, Error> {
-        Ok(self.nodes.get(key).map(Clone::clone))
+    // An in-memory store already owns the nodes, so borrow them with zero copying.
+    fn get_branch(&self, key: &H256) -> Result<Option<Cow<'_, BranchNode>>, Error> {
+        Ok(self.nodes.get(key).map(Cow::Borrowed))
     }
-    fn get_leaf(&self, key: &H256) -> Result<Option<V>, Error> {
-        Ok(self.leaves.get(key).map(Clone::clone))
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>, Error> {
+        Ok(self.leaves.get(key).map(Cow::Borrowed))
     }
 }
 
@@ -0,0 +1,61 @@
+//! Stable identifiers for the hash backend a tree or proof was built with.
+//!
+//! The crate is generic over [`Hasher`](crate::traits::Hasher), but a root or
+//! proof is only meaningful under the exact hash that produced it. Tagging a
+//! serialized tree/proof header with a [`HashAlgorithm`] id lets a decoder
+//! reject a blob it would otherwise verify against with the wrong backend,
+//! silently computing a mismatched root. The Blake2b hasher and, with the
+//! `blake3` feature, the Blake3 hasher advertise their ids through
+//! [`IdentifiedHasher`]; the SHA-256 hasher has no id.
+
+use crate::{
+    blake2b::Blake2bHasher,
+    error::{Error, Result},
+    traits::Hasher,
+};
+#[cfg(feature = "blake3")]
+use crate::blake3_hasher::Blake3Hasher;
+
+/// The hash algorithm behind a [`Hasher`], recorded in serialized headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HashAlgorithm {
+    /// CKB-flavoured Blake2b, the crate default.
+    Blake2b = 0,
+    /// Blake3.
+    Blake3 = 1,
+}
+
+impl HashAlgorithm {
+    /// The 1-byte wire identifier.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Recover an algorithm from its wire identifier.
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(HashAlgorithm::Blake2b),
+            1 => Ok(HashAlgorithm::Blake3),
+            other => Err(Error::InvalidCode(other)),
+        }
+    }
+}
+
+/// A [`Hasher`] that knows its own [`HashAlgorithm`] identifier.
+///
+/// Serialization paths bound on this trait embed `ALGORITHM` in the header and
+/// refuse to decode a blob produced by a different backend.
+pub trait IdentifiedHasher: Hasher {
+    /// The algorithm this hasher implements.
+    const ALGORITHM: HashAlgorithm;
+}
+
+impl IdentifiedHasher for Blake2bHasher {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Blake2b;
+}
+
+#[cfg(feature = "blake3")]
+impl IdentifiedHasher for Blake3Hasher {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Blake3;
+}
@@ -0,0 +1,235 @@
+//! Retained history with a Merkle Mountain Range accumulator over past roots.
+//!
+//! `test_access_history` shows old roots stay queryable while the store keeps
+//! the shared branches; this gives that a first-class API. Each `commit` appends
+//! the resulting root to an append-only [`Mmr`] of roots and keeps the branches
+//! older roots still reference, and [`AuditableSmt::view_at`] reproduces
+//! membership / non-membership proofs against any historical root.
+//!
+//! The MMR is a list of perfect-binary-tree peaks: appending a root adds it as a
+//! leaf, then repeatedly merges with the preceding peak of equal height using
+//! the crate's [`Hasher`]. The bagged hash of the peaks is the history
+//! commitment, and [`AuditableSmt::prove_version`] returns the inclusion path for
+//! a version plus the remaining peaks so a verifier can confirm "root R was the
+//! tree state at version v" against that commitment.
+
+use core::marker::PhantomData;
+
+use crate::{
+    collections::BTreeMap,
+    error::Result,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, Value},
+    vec::Vec,
+    versioned::{Version, VersionedSparseMerkleTree},
+    H256,
+};
+
+/// Hash an ordered pair of MMR node hashes.
+fn hash_nodes<H: Hasher + Default>(left: &H256, right: &H256) -> H256 {
+    let mut hasher = H::default();
+    hasher.write_h256(left);
+    hasher.write_h256(right);
+    hasher.finish()
+}
+
+/// Collapse a perfect subtree of `leaves` (a power-of-two count) to its root.
+fn subtree_root<H: Hasher + Default>(leaves: &[H256]) -> H256 {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let mut next = Vec::with_capacity(level.len() / 2);
+        let mut i = 0;
+        while i < level.len() {
+            next.push(hash_nodes::<H>(&level[i], &level[i + 1]));
+            i += 2;
+        }
+        level = next;
+    }
+    level.first().copied().unwrap_or_else(H256::zero)
+}
+
+/// The sizes of the perfect subtrees making up `n` leaves, largest first.
+fn peak_sizes(n: usize) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    for bit in (0..usize::BITS).rev() {
+        if (n >> bit) & 1 == 1 {
+            sizes.push(1usize << bit);
+        }
+    }
+    sizes
+}
+
+/// An inclusion proof for one version within the history MMR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryProof {
+    /// Sibling hashes from the leaf up to its peak; `true` means the sibling is
+    /// the right child at that level.
+    pub path: Vec<(bool, H256)>,
+    /// All peak hashes of the MMR, left to right.
+    pub peaks: Vec<H256>,
+    /// Which peak the proved leaf folds up into.
+    pub peak_index: usize,
+}
+
+impl HistoryProof {
+    /// Check that `root` was the leaf behind this proof against `commitment`.
+    pub fn verify<H: Hasher + Default>(&self, root: &H256, commitment: &H256) -> bool {
+        if self.peak_index >= self.peaks.len() {
+            return false;
+        }
+        let mut acc = *root;
+        for (sibling_is_right, sibling) in &self.path {
+            acc = if *sibling_is_right {
+                hash_nodes::<H>(&acc, sibling)
+            } else {
+                hash_nodes::<H>(sibling, &acc)
+            };
+        }
+        acc == self.peaks[self.peak_index] && bag_peaks::<H>(&self.peaks) == *commitment
+    }
+}
+
+/// Bag the peaks right-to-left into a single commitment hash.
+fn bag_peaks<H: Hasher + Default>(peaks: &[H256]) -> H256 {
+    match peaks.split_last() {
+        None => H256::zero(),
+        Some((last, rest)) => rest
+            .iter()
+            .rev()
+            .fold(*last, |acc, peak| hash_nodes::<H>(peak, &acc)),
+    }
+}
+
+/// An append-only Merkle Mountain Range over committed roots.
+#[derive(Debug, Clone)]
+pub struct Mmr<H> {
+    leaves: Vec<H256>,
+    phantom: PhantomData<H>,
+}
+
+impl<H> Default for Mmr<H> {
+    fn default() -> Self {
+        Mmr {
+            leaves: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<H: Hasher + Default> Mmr<H> {
+    /// Number of appended roots.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Whether no root has been appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Append a root as a new leaf, returning its leaf index.
+    pub fn append(&mut self, root: H256) -> usize {
+        let index = self.leaves.len();
+        self.leaves.push(root);
+        index
+    }
+
+    /// The current peak hashes, left to right.
+    pub fn peaks(&self) -> Vec<H256> {
+        let mut peaks = Vec::new();
+        let mut start = 0;
+        for size in peak_sizes(self.leaves.len()) {
+            peaks.push(subtree_root::<H>(&self.leaves[start..start + size]));
+            start += size;
+        }
+        peaks
+    }
+
+    /// The bagged history commitment over all peaks.
+    pub fn commitment(&self) -> H256 {
+        bag_peaks::<H>(&self.peaks())
+    }
+
+    /// Produce an inclusion proof for leaf `index`.
+    pub fn prove(&self, index: usize) -> Option<HistoryProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+        let peaks = self.peaks();
+        // Locate the peak (perfect subtree) containing `index`.
+        let mut start = 0;
+        for (peak_index, size) in peak_sizes(self.leaves.len()).into_iter().enumerate() {
+            if index < start + size {
+                let block = &self.leaves[start..start + size];
+                let path = subtree_path::<H>(block, index - start);
+                return Some(HistoryProof {
+                    path,
+                    peaks,
+                    peak_index,
+                });
+            }
+            start += size;
+        }
+        None
+    }
+}
+
+/// The leaf-to-peak sibling path of `local` within a perfect subtree.
+fn subtree_path<H: Hasher + Default>(block: &[H256], local: usize) -> Vec<(bool, H256)> {
+    let mut path = Vec::new();
+    let mut level = block.to_vec();
+    let mut pos = local;
+    while level.len() > 1 {
+        let sibling = if pos % 2 == 0 {
+            (true, level[pos + 1])
+        } else {
+            (false, level[pos - 1])
+        };
+        path.push(sibling);
+        let mut next = Vec::with_capacity(level.len() / 2);
+        let mut i = 0;
+        while i < level.len() {
+            next.push(hash_nodes::<H>(&level[i], &level[i + 1]));
+            i += 2;
+        }
+        level = next;
+        pos /= 2;
+    }
+    path
+}
+
+/// A versioned tree whose committed roots accumulate into a history MMR.
+#[derive(Default)]
+pub struct AuditableSmt<H, V> {
+    inner: VersionedSparseMerkleTree<H, V>,
+    mmr: Mmr<H>,
+    version_of_root: BTreeMap<H256, Version>,
+}
+
+impl<H: Hasher + Default, V: Value + Clone> AuditableSmt<H, V> {
+    /// Apply a batch of updates, commit the new root and record it in the MMR.
+    pub fn commit(&mut self, leaves: Vec<(H256, V)>) -> Result<H256> {
+        let root = self.inner.update_all(leaves)?;
+        self.mmr.append(root);
+        self.version_of_root.insert(root, self.inner.version());
+        Ok(root)
+    }
+
+    /// The history commitment over every root committed so far.
+    pub fn history_commitment(&self) -> H256 {
+        self.mmr.commitment()
+    }
+
+    /// Generate a membership / non-membership proof against a historical `root`.
+    pub fn view_at(&self, root: &H256, keys: Vec<H256>) -> Option<Result<MerkleProof>> {
+        self.version_of_root
+            .get(root)
+            .map(|version| self.inner.merkle_proof_at(*version, keys))
+    }
+
+    /// The MMR inclusion proof for version `v` (its leaf index is `v - 1`).
+    pub fn prove_version(&self, v: Version) -> Option<HistoryProof> {
+        let index = usize::try_from(v).ok()?.checked_sub(1)?;
+        self.mmr.prove(index)
+    }
+}
@@ -0,0 +1,70 @@
+//! Deferred root recomputation.
+//!
+//! [`LazySmt`] defers the expensive bottom-up walk that `update` normally runs
+//! for every key. `update` only records the new leaf value and marks the key
+//! dirty; [`flush`](LazySmt::flush) then drains the whole dirty set through the
+//! same neighbour-merging loop as `SparseMerkleTree::update_all`, so shared
+//! ancestors of many dirty leaves are merged and stored exactly once. The root
+//! returned after `flush` is bit-for-bit identical to applying each `update`
+//! eagerly.
+
+use crate::{
+    collections::Map,
+    error::Result,
+    traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
+    tree::SparseMerkleTree,
+    vec::Vec,
+    H256,
+};
+
+/// A tree wrapper that batches updates until [`flush`](LazySmt::flush).
+#[derive(Default)]
+pub struct LazySmt<H, V, S> {
+    tree: SparseMerkleTree<H, V, S>,
+    dirty: Map<H256, V>,
+}
+
+impl<H, V, S> LazySmt<H, V, S> {
+    /// Wrap an existing tree.
+    pub fn new(tree: SparseMerkleTree<H, V, S>) -> Self {
+        LazySmt {
+            tree,
+            dirty: Default::default(),
+        }
+    }
+
+    /// The root as of the last `flush`.
+    pub fn root(&self) -> &H256 {
+        self.tree.root()
+    }
+
+    /// Number of keys awaiting recomputation.
+    pub fn dirty_len(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Unwrap the inner tree, dropping any unflushed dirty keys.
+    pub fn into_inner(self) -> SparseMerkleTree<H, V, S> {
+        self.tree
+    }
+}
+
+impl<H: Hasher + Default, V: Value + Clone, S: StoreReadOps<V> + StoreWriteOps<V>>
+    LazySmt<H, V, S>
+{
+    /// Record a new value for `key` without recomputing the root yet.
+    ///
+    /// The latest value for a key wins; only the most recent one is flushed.
+    pub fn update(&mut self, key: H256, value: V) {
+        self.dirty.insert(key, value);
+    }
+
+    /// Recompute the root once over the whole dirty set and clear it.
+    pub fn flush(&mut self) -> Result<&H256> {
+        if self.dirty.is_empty() {
+            return Ok(self.tree.root());
+        }
+        let leaves: Vec<(H256, V)> = core::mem::take(&mut self.dirty).into_iter().collect();
+        self.tree.update_all(leaves)
+    }
+}
@@ -0,0 +1,58 @@
+//! A compact, self-contained opening for a single key.
+//!
+//! Unlike the batch [`MerkleProof`](crate::merkle_proof::MerkleProof) — which is
+//! optimized for multi-key proofs with a shared sibling stack — a [`MerklePath`]
+//! carries one sibling per tree height, ordered leaf-to-root, and verifies on
+//! its own with no bitmap bookkeeping. A zero sibling is stored as `None`, and a
+//! zero folded value also proves non-inclusion.
+
+use crate::{
+    merge::{merge, MergeValue},
+    traits::Hasher,
+    vec::Vec,
+    H256,
+};
+
+/// A single-key authentication path, ordered from leaf to root.
+///
+/// `siblings[h]` is the sibling `MergeValue` at height `h`, or `None` when the
+/// sibling is the zero subtree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerklePath {
+    siblings: Vec<Option<MergeValue>>,
+}
+
+impl MerklePath {
+    /// Create a path from its per-height siblings (leaf-to-root).
+    pub fn new(siblings: Vec<Option<MergeValue>>) -> Self {
+        MerklePath { siblings }
+    }
+
+    /// The per-height siblings, leaf-to-root.
+    pub fn siblings(&self) -> &Vec<Option<MergeValue>> {
+        &self.siblings
+    }
+
+    /// Recompute the root this path folds to, given `key` and `value`.
+    ///
+    /// A zero `value` witnesses non-inclusion of `key`.
+    pub fn compute_root<H: Hasher + Default>(&self, key: H256, value: H256) -> H256 {
+        let mut current = MergeValue::from_h256(value);
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            let height = height as u8;
+            let parent_key = key.parent_path(height);
+            let sibling = sibling.clone().unwrap_or_else(MergeValue::zero);
+            current = if key.is_right(height) {
+                merge::<H>(height, &parent_key, &sibling, &current)
+            } else {
+                merge::<H>(height, &parent_key, &current, &sibling)
+            };
+        }
+        current.hash::<H>()
+    }
+
+    /// Verify that `key`/`value` folds to `root` along this path.
+    pub fn verify<H: Hasher + Default>(&self, root: &H256, key: H256, value: H256) -> bool {
+        &self.compute_root::<H>(key, value) == root
+    }
+}
@@ -181,12 +181,157 @@ impl MerkleProof {
         let calculated_root = self.compute_root::<H>(leaves)?;
         Ok(&calculated_root == root)
     }
+
+    /// Compute root treating `None` values as proven-absent (deleted) keys.
+    ///
+    /// `None` maps to the zero-value merge path and `Some(v)` to the present
+    /// value, so a single proof can attest both membership and non-membership.
+    pub fn compute_root_with_options<H: Hasher + Default>(
+        self,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<H256> {
+        self.compute_root::<H>(into_optional_leaves(leaves))
+    }
+
+    /// Verify a proof that mixes present (`Some`) and absent (`None`) keys.
+    pub fn verify_with_options<H: Hasher + Default>(
+        self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<bool> {
+        let calculated_root = self.compute_root_with_options::<H>(leaves)?;
+        Ok(&calculated_root == root)
+    }
+
+    /// Verify a mixed present/absent proof and return a per-key existence bit
+    /// alongside the overall result.
+    ///
+    /// The returned vector parallels `leaves`: each bit is `true` when the key
+    /// is proven present (`Some` non-zero value) and `false` when it is proven
+    /// absent. The bits are only meaningful when the boolean is `true`; a root
+    /// mismatch yields `(false, _)` so "zero" and "absent" are never conflated.
+    pub fn verify_with_existence<H: Hasher + Default>(
+        self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<(bool, Vec<bool>)> {
+        let existence = existence_bits(&leaves);
+        let matched = self.verify_with_options::<H>(root, leaves)?;
+        Ok((matched, existence))
+    }
+
+    /// Verify membership assertions, keeping non-membership first-class.
+    ///
+    /// Each entry asserts a state rather than merely supplying a value:
+    /// `Some(v)` claims the key is present with value `v`, and `None` claims the
+    /// key is absent. Because a zero leaf is exactly how the tree encodes a
+    /// tombstone, a `Some(H256::zero())` assertion claims membership over a
+    /// tombstone and is rejected with [`Error::CorruptedProof`] before any root
+    /// is computed; this is the distinction `verify_with_options` cannot draw,
+    /// where `Some(zero)` and `None` collapse to the same zero leaf.
+    pub fn verify_with_assertions<H: Hasher + Default>(
+        self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<bool> {
+        reject_tombstone_membership(&leaves)?;
+        self.verify_with_options::<H>(root, leaves)
+    }
+}
+
+/// Reject any assertion that claims membership (`Some`) with the zero value,
+/// which the tree can only represent as an absent/tombstoned key.
+fn reject_tombstone_membership(leaves: &[(H256, Option<H256>)]) -> Result<()> {
+    if leaves.iter().any(|(_, v)| matches!(v, Some(v) if v.is_zero())) {
+        return Err(Error::CorruptedProof);
+    }
+    Ok(())
+}
+
+/// The per-key existence bits of a mixed present/absent leaf set: `true` for a
+/// present (`Some` non-zero) key and `false` for an absent one.
+fn existence_bits(leaves: &[(H256, Option<H256>)]) -> Vec<bool> {
+    leaves
+        .iter()
+        .map(|(_, value)| matches!(value, Some(v) if !v.is_zero()))
+        .collect()
+}
+
+/// Map `None` (proven absent) to the zero leaf and `Some(v)` to `v`.
+fn into_optional_leaves(leaves: Vec<(H256, Option<H256>)>) -> Vec<(H256, H256)> {
+    leaves
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_else(H256::zero)))
+        .collect()
+}
+
+/// Per-key outcome of [`MerkleProof::verify_membership`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MembershipStatus {
+    /// The key is present with this (non-zero) value.
+    Included(H256),
+    /// The key is proven absent (its leaf resolved to zero).
+    Excluded,
+    /// The claimed value is inconsistent with the proven root.
+    Mismatch,
+}
+
+impl MerkleProof {
+    /// Verify a batch of membership/non-membership queries and return an
+    /// explicit status per key.
+    ///
+    /// Each query is a key plus an optional expected value (`None` asserts
+    /// absence). The root is computed once and must equal `root`; when it does,
+    /// every key resolving to zero is reported `Excluded` and every other key
+    /// `Included`. A root mismatch reports `Mismatch` for all queries.
+    pub fn verify_membership<H: Hasher + Default>(
+        self,
+        root: &H256,
+        queries: Vec<(H256, Option<H256>)>,
+    ) -> Result<Vec<MembershipStatus>> {
+        let leaves = into_optional_leaves(queries.clone());
+        let calculated_root = self.compute_root::<H>(leaves)?;
+        if &calculated_root != root {
+            return Ok(queries.iter().map(|_| MembershipStatus::Mismatch).collect());
+        }
+        Ok(queries
+            .into_iter()
+            .map(|(_key, value)| match value {
+                Some(v) if !v.is_zero() => MembershipStatus::Included(v),
+                _ => MembershipStatus::Excluded,
+            })
+            .collect())
+    }
 }
 
 /// An structure optimized for verify merkle proof
 #[derive(Debug, Clone)]
 pub struct CompiledMerkleProof(pub Vec<u8>);
 
+/// Receives an ordered event for each step of proof verification.
+///
+/// Used by [`CompiledMerkleProof::compute_root_with_trace`] to extract the full
+/// list of intermediate nodes and merge operands (e.g. for ZK witnesses). All
+/// methods default to no-ops so implementors override only what they need.
+pub trait ProofVisitor {
+    /// A leaf value was pushed onto the stack.
+    fn leaf(&mut self, _key: &H256) {}
+    /// The current subtree at `height` was merged with a proof `sibling`.
+    fn merge_sibling(&mut self, _key: &H256, _height: u8, _sibling: &MergeValue) {}
+    /// Two child subtrees on the stack were merged at `height`.
+    fn stack_merge(
+        &mut self,
+        _key_a: &H256,
+        _key_b: &H256,
+        _height: u8,
+        _value_a: &MergeValue,
+        _value_b: &MergeValue,
+    ) {
+    }
+    /// The current subtree was folded with a run of `n` zero siblings from `height`.
+    fn zero_run(&mut self, _key: &H256, _height: u8, _n: u8) {}
+}
+
 // A op code context passing to the callback function
 enum OpCodeContext<'a> {
     L {
@@ -540,6 +685,210 @@ impl CompiledMerkleProof {
         self.compute_root_inner::<H, _>(leaves, |_| Ok(()))
     }
 
+    /// Verify `old_root` and compute the new root implied by updating some of the
+    /// covered leaves, from the proof alone and with no access to the store.
+    ///
+    /// Each entry is `(key, old_value, new_value)`. The proof carries the
+    /// siblings along every covered path; those siblings are untouched by an
+    /// update to the covered keys, so folding the old leaf hashes upward must
+    /// reproduce `old_root`, and folding the new leaf hashes through the very
+    /// same program yields the post-update root. This lets a constrained
+    /// verifier validate a batch state change by checking only
+    /// `old_root -> new_root` instead of rebuilding the tree.
+    pub fn transition<H: Hasher + Default>(
+        &self,
+        old_root: &H256,
+        updates: Vec<(H256, H256, H256)>,
+    ) -> Result<H256> {
+        let old_leaves = updates
+            .iter()
+            .map(|(key, old_value, _)| (*key, *old_value))
+            .collect();
+        if &self.compute_root::<H>(old_leaves)? != old_root {
+            return Err(Error::CorruptedProof);
+        }
+        let new_leaves = updates
+            .into_iter()
+            .map(|(key, _, new_value)| (key, new_value))
+            .collect();
+        self.compute_root::<H>(new_leaves)
+    }
+
+    /// Recompute the root while emitting a typed event for every step, so
+    /// callers can build arithmetic-circuit witnesses or audit exactly which
+    /// siblings were consumed without re-exposing the crate's internals.
+    pub fn compute_root_with_trace<H: Hasher + Default, P: ProofVisitor>(
+        &self,
+        leaves: Vec<(H256, H256)>,
+        visitor: &mut P,
+    ) -> Result<H256> {
+        let program = &self.0;
+        self.compute_root_inner::<H, _>(leaves, |ctx| {
+            match ctx {
+                OpCodeContext::L { key } => visitor.leaf(key),
+                OpCodeContext::P {
+                    key,
+                    height,
+                    program_index,
+                } => {
+                    let mut data = [0u8; 32];
+                    data.copy_from_slice(&program[program_index - 32..program_index]);
+                    visitor.merge_sibling(
+                        key,
+                        height,
+                        &MergeValue::from_h256(H256::from(data)),
+                    );
+                }
+                OpCodeContext::Q {
+                    key,
+                    height,
+                    program_index,
+                } => {
+                    let zero_count = program[program_index - 65];
+                    let mut base = [0u8; 32];
+                    base.copy_from_slice(&program[program_index - 64..program_index - 32]);
+                    let mut bits = [0u8; 32];
+                    bits.copy_from_slice(&program[program_index - 32..program_index]);
+                    let sibling = MergeValue::MergeWithZero {
+                        base_node: H256::from(base),
+                        zero_bits: H256::from(bits),
+                        zero_count,
+                        value: H256::zero(),
+                    };
+                    visitor.merge_sibling(key, height, &sibling);
+                }
+                OpCodeContext::H {
+                    key_a,
+                    key_b,
+                    height,
+                    value_a,
+                    value_b,
+                } => visitor.stack_merge(key_a, key_b, height, value_a, value_b),
+                OpCodeContext::O { key, height, n } => visitor.zero_run(key, height, n),
+            }
+            Ok(())
+        })
+    }
+
+    /// Fuse several proofs produced separately against the *same* root into one
+    /// compiled program verifying all leaves together, deduplicating shared
+    /// siblings. Each input carries the leaves it covers. Returns
+    /// `Error::CorruptedProof` if two inputs disagree about a shared sibling.
+    pub fn merge<H: Hasher + Default>(
+        proofs: Vec<(CompiledMerkleProof, Vec<(H256, H256)>)>,
+    ) -> Result<CompiledMerkleProof> {
+        use crate::collections::BTreeMap;
+
+        // Record, per (parent_path, height), the sibling MergeValue consumed while
+        // replaying each input proof. Disagreement means the inputs are incompatible.
+        let mut siblings: BTreeMap<(H256, u8), MergeValue> = BTreeMap::new();
+        let mut all_leaves: BTreeMap<H256, H256> = BTreeMap::new();
+
+        for (proof, leaves) in &proofs {
+            for (key, value) in leaves {
+                all_leaves.insert(*key, *value);
+            }
+            let mut record = |ctx: OpCodeContext| -> Result<()> {
+                let (key, height, sibling): (&H256, u8, Option<MergeValue>) = match ctx {
+                    OpCodeContext::P {
+                        key,
+                        height,
+                        program_index,
+                    } => {
+                        let mut data = [0u8; 32];
+                        data.copy_from_slice(&proof.0[program_index - 32..program_index]);
+                        (key, height, Some(MergeValue::from_h256(H256::from(data))))
+                    }
+                    OpCodeContext::Q {
+                        key,
+                        height,
+                        program_index,
+                    } => {
+                        let zero_count = proof.0[program_index - 65];
+                        let mut base = [0u8; 32];
+                        base.copy_from_slice(&proof.0[program_index - 64..program_index - 32]);
+                        let mut bits = [0u8; 32];
+                        bits.copy_from_slice(&proof.0[program_index - 32..program_index]);
+                        (
+                            key,
+                            height,
+                            Some(MergeValue::MergeWithZero {
+                                base_node: H256::from(base),
+                                zero_bits: H256::from(bits),
+                                zero_count,
+                                value: H256::zero(),
+                            }),
+                        )
+                    }
+                    _ => return Ok(()),
+                };
+                if let Some(sibling) = sibling {
+                    let entry = siblings.entry((key.parent_path(height), height));
+                    match entry {
+                        crate::collections::btree_map::Entry::Occupied(o) => {
+                            if *o.get() != sibling {
+                                return Err(Error::CorruptedProof);
+                            }
+                        }
+                        crate::collections::btree_map::Entry::Vacant(v) => {
+                            v.insert(sibling);
+                        }
+                    }
+                }
+                Ok(())
+            };
+            proof.compute_root_inner::<H, _>(leaves.clone(), &mut record)?;
+        }
+
+        // Rebuild a combined MerkleProof by replaying merkle_proof's stack walk
+        // over the union of keys, pulling siblings from the recorded map so a
+        // sibling shared by two leaves is emitted only once.
+        let keys: Vec<H256> = all_leaves.keys().copied().collect();
+        let mut leaves_bitmap: Vec<H256> = Vec::with_capacity(keys.len());
+        for key in &keys {
+            let mut bitmap = H256::zero();
+            for height in 0..=u8::MAX {
+                if siblings.contains_key(&(key.parent_path(height), height)) {
+                    bitmap.set_bit(height);
+                }
+            }
+            leaves_bitmap.push(bitmap);
+        }
+
+        let mut merkle_path: Vec<MergeValue> = Vec::new();
+        let mut stack_fork_height = [0u8; MAX_STACK_SIZE];
+        let mut stack_top = 0;
+        let mut leaf_index = 0;
+        while leaf_index < keys.len() {
+            let leaf_key = keys[leaf_index];
+            let fork_height = if leaf_index + 1 < keys.len() {
+                leaf_key.fork_height(&keys[leaf_index + 1])
+            } else {
+                u8::MAX
+            };
+            for height in 0..=fork_height {
+                if height == fork_height && leaf_index + 1 < keys.len() {
+                    break;
+                }
+                if stack_top > 0 && stack_fork_height[stack_top - 1] == height {
+                    stack_top -= 1;
+                } else if leaves_bitmap[leaf_index].get_bit(height) {
+                    let parent_path = leaf_key.parent_path(height);
+                    let sibling = siblings
+                        .get(&(parent_path, height))
+                        .ok_or(Error::CorruptedProof)?;
+                    merkle_path.push(sibling.clone());
+                }
+            }
+            debug_assert!(stack_top < MAX_STACK_SIZE);
+            stack_fork_height[stack_top] = fork_height;
+            stack_top += 1;
+            leaf_index += 1;
+        }
+
+        MerkleProof::new(leaves_bitmap, merkle_path).compile(keys)
+    }
+
     pub fn verify<H: Hasher + Default>(
         &self,
         root: &H256,
@@ -548,6 +897,170 @@ impl CompiledMerkleProof {
         let calculated_root = self.compute_root::<H>(leaves)?;
         Ok(&calculated_root == root)
     }
+
+    /// Compute root treating `None` values as proven-absent (deleted) keys.
+    pub fn compute_root_with_options<H: Hasher + Default>(
+        &self,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<H256> {
+        self.compute_root::<H>(into_optional_leaves(leaves))
+    }
+
+    /// Verify a compiled proof that mixes present (`Some`) and absent (`None`) keys.
+    pub fn verify_with_options<H: Hasher + Default>(
+        &self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<bool> {
+        let calculated_root = self.compute_root_with_options::<H>(leaves)?;
+        Ok(&calculated_root == root)
+    }
+
+    /// Verify a mixed present/absent compiled proof and return a per-key
+    /// existence bit alongside the overall result; see
+    /// [`MerkleProof::verify_with_existence`].
+    pub fn verify_with_existence<H: Hasher + Default>(
+        &self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<(bool, Vec<bool>)> {
+        let existence = existence_bits(&leaves);
+        let matched = self.verify_with_options::<H>(root, leaves)?;
+        Ok((matched, existence))
+    }
+
+    /// Verify membership assertions on a compiled proof; see
+    /// [`MerkleProof::verify_with_assertions`].
+    pub fn verify_with_assertions<H: Hasher + Default>(
+        &self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<bool> {
+        reject_tombstone_membership(&leaves)?;
+        self.verify_with_options::<H>(root, leaves)
+    }
+}
+
+/// A decoded opcode of a [`CompiledMerkleProof`] bytecode program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofOp {
+    /// `0x4C` push a leaf value.
+    PushLeaf,
+    /// `0x50` merge the stack top with a plain-value sibling.
+    MergeValue(H256),
+    /// `0x51` merge the stack top with a merge-with-zero sibling.
+    MergeWithZero {
+        zero_count: u8,
+        base_node: H256,
+        zero_bits: H256,
+    },
+    /// `0x52` merge the stack top with a short-cut sibling value.
+    ShortCut(H256),
+    /// `0x48` merge the two top stack items.
+    Merge,
+    /// `0x4F` fold the stack top with a run of `n` zeros (`n == 0` means 256).
+    ZeroRun(u8),
+}
+
+impl CompiledMerkleProof {
+    /// Parse the byte stream back into a typed op list.
+    pub fn disassemble(&self) -> Result<Vec<ProofOp>> {
+        let program = &self.0;
+        let mut ops = Vec::new();
+        let mut i = 0;
+        while i < program.len() {
+            let code = program[i];
+            i += 1;
+            match code {
+                0x4C => ops.push(ProofOp::PushLeaf),
+                0x50 => {
+                    let data = read_hash(program, &mut i)?;
+                    ops.push(ProofOp::MergeValue(data));
+                }
+                0x52 => {
+                    let data = read_hash(program, &mut i)?;
+                    ops.push(ProofOp::ShortCut(data));
+                }
+                0x51 => {
+                    if i + 65 > program.len() {
+                        return Err(Error::CorruptedProof);
+                    }
+                    let zero_count = program[i];
+                    let base_node = H256::from(<[u8; 32]>::try_from(&program[i + 1..i + 33]).unwrap());
+                    let zero_bits = H256::from(<[u8; 32]>::try_from(&program[i + 33..i + 65]).unwrap());
+                    i += 65;
+                    ops.push(ProofOp::MergeWithZero {
+                        zero_count,
+                        base_node,
+                        zero_bits,
+                    });
+                }
+                0x48 => ops.push(ProofOp::Merge),
+                0x4F => {
+                    if i >= program.len() {
+                        return Err(Error::CorruptedProof);
+                    }
+                    let n = program[i];
+                    i += 1;
+                    ops.push(ProofOp::ZeroRun(n));
+                }
+                _ => return Err(Error::InvalidCode(code)),
+            }
+        }
+        Ok(ops)
+    }
+
+    /// Check structural well-formedness without any leaves: balanced stack,
+    /// every `P/Q/O` has a pushed item, every `H` two same-height operands,
+    /// offsets stay in bounds, and the program ends with one entry at height 256.
+    pub fn validate(&self) -> Result<()> {
+        let mut stack: Vec<u16> = Vec::new();
+        for op in self.disassemble()? {
+            match op {
+                ProofOp::PushLeaf => stack.push(0),
+                ProofOp::MergeValue(_) | ProofOp::MergeWithZero { .. } | ProofOp::ShortCut(_) => {
+                    let height = stack.pop().ok_or(Error::CorruptedStack)?;
+                    if height >= 256 {
+                        return Err(Error::CorruptedProof);
+                    }
+                    stack.push(height + 1);
+                }
+                ProofOp::Merge => {
+                    let b = stack.pop().ok_or(Error::CorruptedStack)?;
+                    let a = stack.pop().ok_or(Error::CorruptedStack)?;
+                    if a != b || a >= 256 {
+                        return Err(Error::CorruptedProof);
+                    }
+                    stack.push(a + 1);
+                }
+                ProofOp::ZeroRun(n) => {
+                    let height = stack.pop().ok_or(Error::CorruptedStack)?;
+                    let count: u16 = if n == 0 { 256 } else { n as u16 };
+                    if height + count > 256 {
+                        return Err(Error::CorruptedProof);
+                    }
+                    stack.push(height + count);
+                }
+            }
+        }
+        if stack.len() != 1 {
+            return Err(Error::CorruptedStack);
+        }
+        if stack[0] != 256 {
+            return Err(Error::CorruptedProof);
+        }
+        Ok(())
+    }
+}
+
+/// Read a 32-byte hash at `*offset`, advancing it.
+fn read_hash(program: &[u8], offset: &mut usize) -> Result<H256> {
+    if *offset + 32 > program.len() {
+        return Err(Error::CorruptedProof);
+    }
+    let data = H256::from(<[u8; 32]>::try_from(&program[*offset..*offset + 32]).unwrap());
+    *offset += 32;
+    Ok(data)
 }
 
 impl From<CompiledMerkleProof> for Vec<u8> {
@@ -0,0 +1,216 @@
+//! Many independent sparse merkle trees sharing a single backing store.
+//!
+//! [`VersionedSMT`] keeps one logical [`SparseMerkleTree`] per application-chosen
+//! id while persisting every tree's branches and leaves in one
+//! [`MultiTreeStore`]. Each store entry is namespaced by its owning id, so the
+//! trees never collide and the whole set reconstructs from a single persisted
+//! store via [`VersionedSMT::from_store`]. This keeps thousands of small
+//! per-owner trees in one database without a [`DefaultStore`] allocation per
+//! tree.
+//!
+//! [`DefaultStore`]: crate::default_store::DefaultStore
+
+use core::marker::PhantomData;
+
+use crate::{
+    borrow::Cow,
+    collections::BTreeMap,
+    error::Result,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
+    tree::{BranchKey, BranchNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+
+/// An application-chosen tree identifier used to namespace store entries.
+pub type TreeId = Vec<u8>;
+
+/// A store holding the branches and leaves of many trees, each entry keyed by
+/// `(id, node_key)` so distinct trees share the same map without colliding.
+#[derive(Debug, Clone, Default)]
+pub struct MultiTreeStore<V> {
+    branches: BTreeMap<(TreeId, BranchKey), BranchNode>,
+    leaves: BTreeMap<(TreeId, H256), V>,
+}
+
+impl<V> MultiTreeStore<V> {
+    /// The ids of every tree that has at least one branch recorded.
+    pub fn tree_ids(&self) -> Vec<TreeId> {
+        let mut ids: Vec<TreeId> = Vec::new();
+        for (id, _) in self.branches.keys() {
+            if ids.last() != Some(id) {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
+}
+
+/// A single tree's view over the shared store, prefixing every access with `id`.
+struct NamespaceView<'a, V> {
+    id: TreeId,
+    store: &'a mut MultiTreeStore<V>,
+}
+
+impl<V: Clone> StoreReadOps<V> for NamespaceView<'_, V> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        Ok(self
+            .store
+            .branches
+            .get(&(self.id.clone(), key.clone()))
+            .map(Cow::Borrowed))
+    }
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        Ok(self
+            .store
+            .leaves
+            .get(&(self.id.clone(), *key))
+            .map(Cow::Borrowed))
+    }
+}
+
+impl<V: Clone> StoreWriteOps<V> for NamespaceView<'_, V> {
+    fn insert_branch(&mut self, key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.store.branches.insert((self.id.clone(), key), branch);
+        Ok(())
+    }
+    fn insert_leaf(&mut self, key: H256, leaf: V) -> Result<()> {
+        self.store.leaves.insert((self.id.clone(), key), leaf);
+        Ok(())
+    }
+    fn remove_branch(&mut self, key: &BranchKey) -> Result<()> {
+        self.store.branches.remove(&(self.id.clone(), key.clone()));
+        Ok(())
+    }
+    fn remove_leaf(&mut self, key: &H256) -> Result<()> {
+        self.store.leaves.remove(&(self.id.clone(), *key));
+        Ok(())
+    }
+}
+
+/// A collection of independent sparse merkle trees over one shared store.
+#[derive(Default)]
+pub struct VersionedSMT<H, V> {
+    store: MultiTreeStore<V>,
+    roots: BTreeMap<TreeId, H256>,
+    phantom: PhantomData<H>,
+}
+
+impl<H: Hasher + Default, V: Value + Clone> VersionedSMT<H, V> {
+    /// An empty subsystem with no trees.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild every tree's root from a persisted store, mirroring the
+    /// single-tree `from_store` reconstruction.
+    pub fn from_store(store: MultiTreeStore<V>) -> Result<Self> {
+        let mut roots = BTreeMap::new();
+        let mut this = VersionedSMT {
+            store,
+            roots: BTreeMap::new(),
+            phantom: PhantomData,
+        };
+        for id in this.store.tree_ids() {
+            let tree = this.tree(&id, H256::zero());
+            let tree = SparseMerkleTree::<H, V, _>::new_with_store(tree.take_store())?;
+            roots.insert(id, *tree.root());
+        }
+        this.roots = roots;
+        Ok(this)
+    }
+
+    /// Borrow the shared store holding every tree's branches and leaves.
+    pub fn store(&self) -> &MultiTreeStore<V> {
+        &self.store
+    }
+
+    /// The current root of tree `id` (the empty root if it has no entries).
+    pub fn root(&self, id: &[u8]) -> H256 {
+        self.roots.get(id).copied().unwrap_or_else(H256::zero)
+    }
+
+    /// Update `key` in tree `id`, returning the tree's new root.
+    pub fn update(&mut self, id: &[u8], key: H256, value: V) -> Result<H256> {
+        let root = self.root(id);
+        let mut tree = self.tree(id, root);
+        tree.update(key, value)?;
+        let root = *tree.root();
+        self.roots.insert(id.to_vec(), root);
+        Ok(root)
+    }
+
+    /// Read `key` from tree `id`.
+    pub fn get(&mut self, id: &[u8], key: &H256) -> Result<V> {
+        let root = self.root(id);
+        self.tree(id, root).get(key)
+    }
+
+    /// Generate a merkle proof for `keys` against tree `id`.
+    pub fn merkle_proof(&mut self, id: &[u8], keys: Vec<H256>) -> Result<MerkleProof> {
+        let root = self.root(id);
+        self.tree(id, root).merkle_proof(keys)
+    }
+
+    /// A tree over the namespaced view of the shared store at `root`.
+    fn tree(&mut self, id: &[u8], root: H256) -> SparseMerkleTree<H, V, NamespaceView<'_, V>> {
+        let view = NamespaceView {
+            id: id.to_vec(),
+            store: &mut self.store,
+        };
+        SparseMerkleTree::new(root, view)
+    }
+}
+
+/// A fixed-width namespace identifier, e.g. a CKB account/cell id.
+pub type Xid = [u8; 16];
+
+/// An [`xsmt`]-style view over [`VersionedSMT`] that addresses trees by a
+/// fixed-width [`Xid`] instead of a variable-length [`TreeId`].
+///
+/// Many callers (per-account balances, per-cell CKB state) key their trees by a
+/// 16-byte id rather than an arbitrary byte string; [`VsSmt`] gives them that
+/// surface directly while reusing the shared [`MultiTreeStore`] underneath, so
+/// thousands of trees still live in one store without a per-tree allocation.
+///
+/// [`xsmt`]: https://github.com/nervosnetwork/sparse-merkle-tree
+#[derive(Default)]
+pub struct VsSmt<H, V>(VersionedSMT<H, V>);
+
+impl<H: Hasher + Default, V: Value + Clone> VsSmt<H, V> {
+    /// An empty subsystem with no trees.
+    pub fn new() -> Self {
+        VsSmt(VersionedSMT::new())
+    }
+
+    /// Rebuild every tree's root from a persisted store.
+    pub fn from_store(store: MultiTreeStore<V>) -> Result<Self> {
+        VersionedSMT::from_store(store).map(VsSmt)
+    }
+
+    /// The current root of the tree namespaced by `xid`.
+    pub fn root(&self, xid: &Xid) -> H256 {
+        self.0.root(xid)
+    }
+
+    /// Update `key` in the tree namespaced by `xid`, returning its new root.
+    pub fn update(&mut self, xid: &Xid, key: H256, value: V) -> Result<H256> {
+        self.0.update(xid, key, value)
+    }
+
+    /// Read `key` from the tree namespaced by `xid`.
+    pub fn get(&mut self, xid: &Xid, key: &H256) -> Result<V> {
+        self.0.get(xid, key)
+    }
+
+    /// Generate a merkle proof for `keys` against the tree namespaced by `xid`.
+    pub fn merkle_proof(&mut self, xid: &Xid, keys: Vec<H256>) -> Result<MerkleProof> {
+        self.0.merkle_proof(xid, keys)
+    }
+
+    /// Borrow the shared store holding every tree's nodes.
+    pub fn store(&self) -> &MultiTreeStore<V> {
+        self.0.store()
+    }
+}
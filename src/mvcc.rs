@@ -0,0 +1,307 @@
+//! Concurrently-readable, copy-on-write versioned tree.
+//!
+//! Every stored node carries the transaction id that wrote it, so one writer and
+//! many readers share a tree without locking the readers out. [`write_txn`]
+//! batches `update`s under a pending txid, appending new branch/leaf versions
+//! while leaving prior ones intact; `commit` publishes the new root atomically.
+//! [`read_txn`] pins the currently published root and txid and serves
+//! `get`/`merkle_proof` against that snapshot even while a writer runs ahead.
+//! [`reclaim`] drops node versions older than the oldest live reader so the
+//! version chains stay bounded.
+//!
+//! [`write_txn`]: MvccSmt::write_txn
+//! [`read_txn`]: MvccSmt::read_txn
+//! [`reclaim`]: MvccSmt::reclaim
+
+use core::marker::PhantomData;
+
+use crate::{
+    borrow::Cow,
+    default_store::Map,
+    error::Result,
+    traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
+    tree::{BranchKey, BranchNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+
+/// A versioned key-value store tagging every write with a transaction id.
+#[derive(Default)]
+pub struct MvccStore<V> {
+    // Ascending-by-txid chains of `Some(node)` writes and `None` tombstones.
+    branches: Map<BranchKey, Vec<(u64, Option<BranchNode>)>>,
+    leaves: Map<H256, Vec<(u64, Option<V>)>>,
+    published: u64,
+}
+
+fn latest_le<T: Clone>(chain: &[(u64, Option<T>)], txid: u64) -> Option<T> {
+    chain
+        .iter()
+        .rev()
+        .find(|(v, _)| *v <= txid)
+        .and_then(|(_, value)| value.clone())
+}
+
+fn record<T>(chain: &mut Vec<(u64, Option<T>)>, txid: u64, value: Option<T>) {
+    match chain.last_mut() {
+        Some((v, slot)) if *v == txid => *slot = value,
+        _ => chain.push((txid, value)),
+    }
+}
+
+/// Drop versions shadowed for every reader at or after `oldest_live`.
+fn prune_chain<T>(chain: &mut Vec<(u64, Option<T>)>, oldest_live: u64) {
+    if let Some(keep_from) = chain.iter().rposition(|(v, _)| *v <= oldest_live) {
+        if keep_from > 0 {
+            chain.drain(0..keep_from);
+        }
+    }
+}
+
+/// Drop the trailing version if it was written under `txid`.
+fn drop_pending<T>(chain: &mut Vec<(u64, Option<T>)>, txid: u64) {
+    if chain.last().map(|(v, _)| *v) == Some(txid) {
+        chain.pop();
+    }
+}
+
+impl<V> MvccStore<V> {
+    /// The most recently published transaction id.
+    pub fn published(&self) -> u64 {
+        self.published
+    }
+
+    /// Drop every node version strictly older than the newest version that is
+    /// still visible at `oldest_live` txid, keeping chains bounded.
+    pub fn reclaim(&mut self, oldest_live: u64) {
+        for chain in self.branches.values_mut() {
+            prune_chain(chain, oldest_live);
+        }
+        for chain in self.leaves.values_mut() {
+            prune_chain(chain, oldest_live);
+        }
+    }
+}
+
+/// A read-only view of an [`MvccStore`] pinned to a single txid.
+pub struct MvccView<'a, V> {
+    store: &'a MvccStore<V>,
+    txid: u64,
+}
+
+impl<V: Clone> StoreReadOps<V> for MvccView<'_, V> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        Ok(self
+            .store
+            .branches
+            .get(key)
+            .and_then(|chain| latest_le(chain, self.txid))
+            .map(Cow::Owned))
+    }
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        Ok(self
+            .store
+            .leaves
+            .get(key)
+            .and_then(|chain| latest_le(chain, self.txid))
+            .map(Cow::Owned))
+    }
+}
+
+/// A read-write view appending versions under the pending txid.
+pub struct MvccWriteView<'a, V> {
+    store: &'a mut MvccStore<V>,
+    txid: u64,
+}
+
+impl<V: Clone> StoreReadOps<V> for MvccWriteView<'_, V> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        Ok(self
+            .store
+            .branches
+            .get(key)
+            .and_then(|chain| latest_le(chain, self.txid))
+            .map(Cow::Owned))
+    }
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        Ok(self
+            .store
+            .leaves
+            .get(key)
+            .and_then(|chain| latest_le(chain, self.txid))
+            .map(Cow::Owned))
+    }
+}
+
+impl<V: Clone> StoreWriteOps<V> for MvccWriteView<'_, V> {
+    fn insert_branch(&mut self, key: BranchKey, branch: BranchNode) -> Result<()> {
+        record(self.store.branches.entry(key).or_default(), self.txid, Some(branch));
+        Ok(())
+    }
+    fn insert_leaf(&mut self, key: H256, leaf: V) -> Result<()> {
+        record(self.store.leaves.entry(key).or_default(), self.txid, Some(leaf));
+        Ok(())
+    }
+    fn remove_branch(&mut self, key: &BranchKey) -> Result<()> {
+        record(self.store.branches.entry(key.clone()).or_default(), self.txid, None);
+        Ok(())
+    }
+    fn remove_leaf(&mut self, key: &H256) -> Result<()> {
+        record(self.store.leaves.entry(*key).or_default(), self.txid, None);
+        Ok(())
+    }
+}
+
+/// A transactionally versioned sparse Merkle tree.
+pub struct MvccSmt<H, V> {
+    store: MvccStore<V>,
+    // Published root per txid, so a reader at txid T resolves the right root.
+    roots: Map<u64, H256>,
+    root: H256,
+    phantom: PhantomData<H>,
+}
+
+impl<H, V> Default for MvccSmt<H, V>
+where
+    MvccStore<V>: Default,
+{
+    fn default() -> Self {
+        MvccSmt {
+            store: MvccStore::default(),
+            roots: Default::default(),
+            root: H256::zero(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A pinned read snapshot serving queries against a historical root.
+pub struct ReadGuard<'a, H, V> {
+    store: &'a MvccStore<V>,
+    root: H256,
+    txid: u64,
+    phantom: PhantomData<H>,
+}
+
+impl<H: Hasher + Default, V: Value + Clone> ReadGuard<'_, H, V> {
+    /// The root this guard was pinned to.
+    pub fn root(&self) -> &H256 {
+        &self.root
+    }
+
+    fn tree(&self) -> SparseMerkleTree<H, V, MvccView<'_, V>> {
+        SparseMerkleTree::new(
+            self.root,
+            MvccView {
+                store: self.store,
+                txid: self.txid,
+            },
+        )
+    }
+
+    /// Read a leaf against the pinned snapshot.
+    pub fn get(&self, key: &H256) -> Result<V> {
+        self.tree().get(key)
+    }
+
+    /// Generate a proof against the pinned snapshot.
+    pub fn merkle_proof(&self, keys: Vec<H256>) -> Result<crate::merkle_proof::MerkleProof> {
+        self.tree().merkle_proof(keys)
+    }
+}
+
+/// A batched write transaction; `commit` publishes, `drop`/`discard` rolls back.
+pub struct WriteTxn<'a, H, V> {
+    owner: &'a mut MvccSmt<H, V>,
+    txid: u64,
+    root: H256,
+    done: bool,
+}
+
+impl<H: Hasher + Default, V: Value + Clone> WriteTxn<'_, H, V> {
+    /// Apply one update within the transaction.
+    pub fn update(&mut self, key: H256, value: V) -> Result<&H256> {
+        let mut tree = SparseMerkleTree::<H, V, _>::new(
+            self.root,
+            MvccWriteView {
+                store: &mut self.owner.store,
+                txid: self.txid,
+            },
+        );
+        tree.update(key, value)?;
+        self.root = *tree.root();
+        Ok(&self.root)
+    }
+
+    /// Publish the new root atomically, making it visible to new readers.
+    pub fn commit(mut self) {
+        self.owner.store.published = self.txid;
+        self.owner.roots.insert(self.txid, self.root);
+        self.owner.root = self.root;
+        self.done = true;
+    }
+
+    /// Discard every version written under this transaction.
+    pub fn discard(mut self) {
+        self.rollback();
+        self.done = true;
+    }
+
+    fn rollback(&mut self) {
+        let txid = self.txid;
+        for chain in self.owner.store.branches.values_mut() {
+            drop_pending(chain, txid);
+        }
+        for chain in self.owner.store.leaves.values_mut() {
+            drop_pending(chain, txid);
+        }
+    }
+}
+
+impl<H, V> Drop for WriteTxn<'_, H, V> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.rollback();
+        }
+    }
+}
+
+impl<H: Hasher + Default, V: Value + Clone> MvccSmt<H, V> {
+    /// The latest published root.
+    pub fn root(&self) -> &H256 {
+        &self.root
+    }
+
+    /// Borrow the backing store, e.g. to [`reclaim`](MvccStore::reclaim).
+    pub fn store(&self) -> &MvccStore<V> {
+        &self.store
+    }
+
+    /// Pin the published root + txid and serve consistent reads against it.
+    pub fn read_txn(&self) -> ReadGuard<'_, H, V> {
+        ReadGuard {
+            store: &self.store,
+            root: self.root,
+            txid: self.store.published,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Begin a write transaction layered on top of the published state.
+    pub fn write_txn(&mut self) -> WriteTxn<'_, H, V> {
+        let txid = self.store.published + 1;
+        let root = self.root;
+        WriteTxn {
+            owner: self,
+            txid,
+            root,
+            done: false,
+        }
+    }
+
+    /// Reclaim node versions older than the oldest live reader txid.
+    pub fn reclaim(&mut self, oldest_live: u64) {
+        self.store.reclaim(oldest_live);
+        self.roots.retain(|txid, _| *txid >= oldest_live);
+    }
+}
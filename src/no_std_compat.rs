@@ -0,0 +1,51 @@
+//! `alloc`/`std` aliasing so the core tree, proof and hashing code builds under
+//! `no_std` + `alloc`.
+//!
+//! The rest of the crate refers to `crate::vec`, `crate::string`,
+//! `crate::borrow` and `crate::collections` rather than `std::*`; this module
+//! resolves those to `alloc` when `std` is off and to `std` when it is on. The
+//! only `std`-specific map, `HashMap`, falls back to `alloc`'s `BTreeMap` (or
+//! `hashbrown` behind the `hashbrown` feature) so `H256`, `BranchNode`,
+//! `MergeValue`, the `Hasher`/`Value`/`Store` traits and proof verification all
+//! work in constrained environments such as on-chain scripts. Filesystem- or
+//! RocksDB-backed stores stay gated behind `std`.
+
+pub mod vec {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "std")]
+    pub use std::vec::Vec;
+}
+
+pub mod string {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::string::{String, ToString};
+    #[cfg(feature = "std")]
+    pub use std::string::{String, ToString};
+}
+
+pub mod borrow {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::borrow::Cow;
+    #[cfg(feature = "std")]
+    pub use std::borrow::Cow;
+}
+
+pub mod collections {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::collections::{btree_map, BTreeMap, BTreeSet, VecDeque};
+    #[cfg(feature = "std")]
+    pub use std::collections::{btree_map, BTreeMap, BTreeSet, VecDeque};
+
+    // `HashMap` only exists on `std`; under `no_std` fall back to a map that
+    // needs no hasher. `hashbrown` is used when its feature is enabled so hot
+    // lookups keep hashing rather than tree-walking.
+    #[cfg(feature = "std")]
+    pub use std::collections::{hash_map, HashMap};
+
+    #[cfg(all(not(feature = "std"), feature = "hashbrown"))]
+    pub use hashbrown::{hash_map, HashMap};
+
+    #[cfg(all(not(feature = "std"), not(feature = "hashbrown")))]
+    pub use alloc::collections::{btree_map as hash_map, BTreeMap as HashMap};
+}
@@ -0,0 +1,110 @@
+use crate::{
+    borrow::Cow,
+    default_store::Map,
+    error::Result,
+    traits::{StoreReadOps, StoreWriteOps},
+    tree::{BranchKey, BranchNode},
+    H256,
+};
+
+/// A single overlay slot: either a staged value or a tombstone recording that
+/// the key was deleted in the overlay.
+#[derive(Debug, Clone)]
+enum Slot<T> {
+    Value(T),
+    Deleted,
+}
+
+/// A staging layer over an existing store.
+///
+/// All writes land in an in-memory top layer, leaving the wrapped `under` store
+/// untouched until [`commit`](OverlayStore::commit). Reads hit the top layer
+/// first and fall back to the under layer. Deletions are recorded as explicit
+/// tombstones so a key removed in the overlay is never resurrected by a stale
+/// value in the under layer. This gives a mempool-style consumer a throwaway
+/// SMT view over committed state that can be applied with `update`/`update_all`
+/// and then either `commit`ted or `discard`ed.
+#[derive(Debug, Clone, Default)]
+pub struct OverlayStore<V, S> {
+    under: S,
+    branches: Map<BranchKey, Slot<BranchNode>>,
+    leaves: Map<H256, Slot<V>>,
+}
+
+impl<V, S> OverlayStore<V, S> {
+    /// Wrap an under layer with an empty top layer.
+    pub fn new(under: S) -> Self {
+        OverlayStore {
+            under,
+            branches: Default::default(),
+            leaves: Default::default(),
+        }
+    }
+
+    /// Drop the staged top layer, leaving the under layer untouched.
+    pub fn discard(&mut self) {
+        self.branches.clear();
+        self.leaves.clear();
+    }
+
+    /// Unwrap the under layer, dropping any uncommitted staged writes.
+    pub fn into_inner(self) -> S {
+        self.under
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V> + StoreWriteOps<V>> OverlayStore<V, S> {
+    /// Flush every staged write (and tombstone) into the under layer.
+    pub fn commit(&mut self) -> Result<()> {
+        for (key, slot) in core::mem::take(&mut self.branches) {
+            match slot {
+                Slot::Value(node) => self.under.insert_branch(key, node)?,
+                Slot::Deleted => self.under.remove_branch(&key)?,
+            }
+        }
+        for (key, slot) in core::mem::take(&mut self.leaves) {
+            match slot {
+                Slot::Value(leaf) => self.under.insert_leaf(key, leaf)?,
+                Slot::Deleted => self.under.remove_leaf(&key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V>> StoreReadOps<V> for OverlayStore<V, S> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        match self.branches.get(key) {
+            Some(Slot::Value(node)) => Ok(Some(Cow::Borrowed(node))),
+            Some(Slot::Deleted) => Ok(None),
+            None => self.under.get_branch(key),
+        }
+    }
+
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        match self.leaves.get(key) {
+            Some(Slot::Value(leaf)) => Ok(Some(Cow::Borrowed(leaf))),
+            Some(Slot::Deleted) => Ok(None),
+            None => self.under.get_leaf(key),
+        }
+    }
+}
+
+impl<V, S> StoreWriteOps<V> for OverlayStore<V, S> {
+    fn insert_branch(&mut self, key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.branches.insert(key, Slot::Value(branch));
+        Ok(())
+    }
+    fn insert_leaf(&mut self, key: H256, leaf: V) -> Result<()> {
+        self.leaves.insert(key, Slot::Value(leaf));
+        Ok(())
+    }
+    fn remove_branch(&mut self, key: &BranchKey) -> Result<()> {
+        self.branches.insert(key.clone(), Slot::Deleted);
+        Ok(())
+    }
+    fn remove_leaf(&mut self, key: &H256) -> Result<()> {
+        self.leaves.insert(*key, Slot::Deleted);
+        Ok(())
+    }
+}
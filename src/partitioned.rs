@@ -0,0 +1,129 @@
+//! Key-space partitioned tree with independent sub-roots.
+//!
+//! [`PartitionedSMT`] splits the key space into `2^bits` shards by the top
+//! `bits` bits of each key and keeps one independent [`SparseMerkleTree`] per
+//! shard. A small top-level tree, whose leaves are the shard roots, preserves a
+//! single global root. Updates routed to different shards touch disjoint branch
+//! sets, so shards can be updated independently (and in parallel) before the
+//! cheap top-level recombine.
+
+use crate::{
+    default_store::DefaultStore,
+    error::Result,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, Value},
+    tree::SparseMerkleTree,
+    vec::Vec,
+    H256,
+};
+
+type Shard<H, V> = SparseMerkleTree<H, V, DefaultStore<V>>;
+type TopTree<H> = SparseMerkleTree<H, H256, DefaultStore<H256>>;
+
+/// A proof against the global root: the shard opening plus the top-tree opening
+/// that binds the shard root into the global root.
+///
+/// `shard_root` is carried explicitly so a verifier can chain
+/// `shard_proof → top_proof` without re-deriving it out-of-band.
+pub struct PartitionedProof {
+    pub shard_index: usize,
+    pub shard_root: H256,
+    pub shard_proof: MerkleProof,
+    pub top_proof: MerkleProof,
+}
+
+impl PartitionedProof {
+    /// Verify `key`/`value` against the global `root`.
+    ///
+    /// The shard opening must bind `(key, value)` to `shard_root`, and the
+    /// top-tree opening must bind `(top_key(shard_index), shard_root)` to the
+    /// global root, so acceptance reduces the two-level proof to a single root.
+    pub fn verify<H: Hasher + Default>(
+        self,
+        root: &H256,
+        key: H256,
+        value: H256,
+    ) -> Result<bool> {
+        if !self
+            .shard_proof
+            .verify::<H>(&self.shard_root, crate::vec![(key, value)])?
+        {
+            return Ok(false);
+        }
+        self.top_proof
+            .verify::<H>(root, crate::vec![(top_key(self.shard_index), self.shard_root)])
+    }
+}
+
+/// The H256 key under which shard `index`'s root is stored in the top tree.
+fn top_key(index: usize) -> H256 {
+    let mut bytes = [0u8; 32];
+    bytes[0] = index as u8;
+    bytes.into()
+}
+
+/// A sharded sparse merkle tree with a single global root.
+pub struct PartitionedSMT<H, V> {
+    bits: u8,
+    shards: Vec<Shard<H, V>>,
+    top: TopTree<H>,
+}
+
+impl<H: Hasher + Default, V: Value + Clone> PartitionedSMT<H, V> {
+    /// Build an empty partitioned tree with `2^bits` shards (`bits <= 8`).
+    pub fn new(bits: u8) -> Self {
+        assert!(bits <= 8, "shard bits must be <= 8");
+        let shard_count = 1usize << bits;
+        let mut shards = Vec::with_capacity(shard_count);
+        for _ in 0..shard_count {
+            shards.push(Shard::default());
+        }
+        PartitionedSMT {
+            bits,
+            shards,
+            top: TopTree::default(),
+        }
+    }
+
+    /// Index of the shard owning `key` (its top `bits` bits).
+    fn shard_index(&self, key: &H256) -> usize {
+        if self.bits == 0 {
+            0
+        } else {
+            (key.as_slice()[0] >> (8 - self.bits)) as usize
+        }
+    }
+
+    /// The current global root.
+    pub fn root(&self) -> &H256 {
+        self.top.root()
+    }
+
+    /// Route an update to its shard and recombine the top tree.
+    pub fn update(&mut self, key: H256, value: V) -> Result<&H256> {
+        let index = self.shard_index(&key);
+        let shard_root = *self.shards[index].update(key, value)?;
+        self.top.update(top_key(index), shard_root)?;
+        Ok(self.top.root())
+    }
+
+    /// Read a value from its owning shard.
+    pub fn get(&self, key: &H256) -> Result<V> {
+        let index = self.shard_index(key);
+        self.shards[index].get(key)
+    }
+
+    /// Generate a proof that reduces to the global root.
+    pub fn merkle_proof(&self, key: H256) -> Result<PartitionedProof> {
+        let index = self.shard_index(&key);
+        let shard_root = *self.shards[index].root();
+        let shard_proof = self.shards[index].merkle_proof(crate::vec![key])?;
+        let top_proof = self.top.merkle_proof(crate::vec![top_key(index)])?;
+        Ok(PartitionedProof {
+            shard_index: index,
+            shard_root,
+            shard_proof,
+            top_proof,
+        })
+    }
+}
@@ -0,0 +1,188 @@
+//! Range-absence proofs over a contiguous key interval.
+//!
+//! Leaves are ordered by their key bits, so the emptiness of an interval is
+//! witnessed by single-key openings for the immediate boundary keys — the
+//! largest existing key below the interval and the smallest existing key above
+//! it. Membership of a boundary key against the root is *not* on its own enough
+//! to prove the gap empty: a key `m` strictly between the boundaries lives in a
+//! subtree hanging off one of the two boundary paths, and that subtree is a
+//! sibling the boundary opening still commits to. The proof is therefore only
+//! accepted when every *inner-facing* sibling on the two boundary paths — the
+//! ones pointing into the gap, below the height at which the boundaries fork —
+//! is the zero subtree. Because each sibling is bound by the root, a prover
+//! cannot substitute a zero where a real subtree exists without the recomputed
+//! root diverging, so a non-empty interval always fails to verify.
+
+use crate::{
+    merkle_path::MerklePath,
+    traits::Hasher,
+    H256,
+};
+
+/// A closed lexicographic key interval `[start, end]`: both bounds are inside
+/// the range. `None` bounds extend to the edge of the key space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRange {
+    pub start: Option<H256>,
+    pub end: Option<H256>,
+}
+
+impl KeyRange {
+    /// Construct a range from optional bounds.
+    pub fn new(start: Option<H256>, end: Option<H256>) -> Self {
+        KeyRange { start, end }
+    }
+
+    /// Split the range at `mid` so callers can recursively prove large ranges
+    /// in pieces, mirroring the external b-tree `split` helper.
+    pub fn split(self, mid: H256) -> (KeyRange, KeyRange) {
+        (
+            KeyRange::new(self.start, Some(mid)),
+            KeyRange::new(Some(mid), self.end),
+        )
+    }
+}
+
+/// Numeric (big-endian) comparison of two keys.
+fn key_lt(a: &H256, b: &H256) -> bool {
+    a.split() < b.split()
+}
+
+/// A boundary key with its single-key opening against the root.
+///
+/// `key` is the boundary leaf, `value` its stored (non-zero) value, and `path`
+/// the [`MerklePath`] that folds `key`/`value` back to the root.
+#[derive(Debug, Clone)]
+pub struct BoundaryLeaf {
+    pub key: H256,
+    pub value: H256,
+    pub path: MerklePath,
+}
+
+impl BoundaryLeaf {
+    /// Assemble a boundary leaf from its opening.
+    pub fn new(key: H256, value: H256, path: MerklePath) -> Self {
+        BoundaryLeaf { key, value, path }
+    }
+}
+
+/// A proof that a contiguous key interval holds no non-zero key.
+///
+/// `left` is the immediate predecessor of the interval (the largest existing
+/// key below `range.start`) and `right` its immediate successor. A `None`
+/// boundary means the interval runs to that edge of the key space, in which
+/// case the opposite boundary must be the global minimum/maximum leaf.
+#[derive(Debug, Clone)]
+pub struct RangeProof {
+    pub left: Option<BoundaryLeaf>,
+    pub right: Option<BoundaryLeaf>,
+}
+
+impl RangeProof {
+    /// Assemble a range proof from its boundary openings.
+    pub fn new(left: Option<BoundaryLeaf>, right: Option<BoundaryLeaf>) -> Self {
+        RangeProof { left, right }
+    }
+
+    /// Prove that no non-zero key exists in `range` against `root`.
+    pub fn verify<H: Hasher + Default>(
+        &self,
+        root: &H256,
+        range: &KeyRange,
+    ) -> crate::error::Result<bool> {
+        // A present bound requires the matching boundary leaf, and a `None`
+        // bound forbids one: the edge is witnessed by the opposite boundary
+        // being the extreme leaf, checked below.
+        if range.start.is_some() != self.left.is_some()
+            || range.end.is_some() != self.right.is_some()
+        {
+            return Ok(false);
+        }
+
+        // Each supplied boundary must be a genuine non-zero member of the tree.
+        for boundary in [self.left.as_ref(), self.right.as_ref()].into_iter().flatten() {
+            if boundary.value.is_zero()
+                || !boundary.path.verify::<H>(root, boundary.key, boundary.value)
+            {
+                return Ok(false);
+            }
+        }
+
+        // `start <= end` must hold for the interval to be well-formed.
+        if let (Some(start), Some(end)) = (&range.start, &range.end) {
+            if key_lt(end, start) {
+                return Ok(false);
+            }
+        }
+
+        match (&self.left, &self.right) {
+            // Neither bound: the whole key space is empty iff the root is zero.
+            (None, None) => Ok(root.is_zero()),
+            // Lower edge open: `right` must be the global minimum, so nothing
+            // exists below it. `end` (if any) must sit strictly below it.
+            (None, Some(right)) => {
+                if let Some(end) = &range.end {
+                    if !key_lt(end, &right.key) {
+                        return Ok(false);
+                    }
+                }
+                Ok(inner_siblings_empty(&right.path, &right.key, right.path.siblings().len(), false))
+            }
+            // Upper edge open: `left` must be the global maximum.
+            (Some(left), None) => {
+                if let Some(start) = &range.start {
+                    if !key_lt(&left.key, start) {
+                        return Ok(false);
+                    }
+                }
+                Ok(inner_siblings_empty(&left.path, &left.key, left.path.siblings().len(), true))
+            }
+            // Both bounds: the interval sits strictly between two adjacent
+            // leaves. Check the gap-facing siblings below the fork height.
+            (Some(left), Some(right)) => {
+                if !key_lt(&left.key, &right.key) {
+                    return Ok(false);
+                }
+                if let Some(start) = &range.start {
+                    if !key_lt(&left.key, start) {
+                        return Ok(false);
+                    }
+                }
+                if let Some(end) = &range.end {
+                    if !key_lt(end, &right.key) {
+                        return Ok(false);
+                    }
+                }
+                let fork = left.key.fork_height(&right.key);
+                Ok(
+                    inner_siblings_empty(&left.path, &left.key, fork as usize, true)
+                        && inner_siblings_empty(&right.path, &right.key, fork as usize, false),
+                )
+            }
+        }
+    }
+}
+
+/// Check that every gap-facing sibling below `limit` is the zero subtree.
+///
+/// For the left boundary (`left_boundary = true`) the gap lies to the *right* of
+/// the path, so wherever the key is a left child its right sibling must be
+/// empty; for the right boundary the mirror holds. A non-empty sibling there is
+/// a key inside the interval, which the root commits to, so the check rejects it.
+fn inner_siblings_empty(path: &MerklePath, key: &H256, limit: usize, left_boundary: bool) -> bool {
+    let limit = limit.min(path.siblings().len());
+    for (height, sibling) in path.siblings().iter().take(limit).enumerate() {
+        let is_right = key.is_right(height as u8);
+        // The gap-facing sibling is the one opposite the branch the key took:
+        // a left-boundary key facing the gap when it is a left child, and a
+        // right-boundary key when it is a right child.
+        let faces_gap = if left_boundary { !is_right } else { is_right };
+        if faces_gap {
+            match sibling {
+                Some(mv) if !mv.is_zero() => return false,
+                _ => {}
+            }
+        }
+    }
+    true
+}
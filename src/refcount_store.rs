@@ -0,0 +1,274 @@
+//! Content-addressed, reference-counted store with explicit pruning.
+//!
+//! [`RefCountedStore`] wraps any store and tracks, for every distinct branch
+//! or leaf *content* it has ever been given (not tree position), how many
+//! live positions currently point at it. Writing new content to a position
+//! only repoints that position's index entry to the new content's id — the
+//! position's previous content is never overwritten in place, so it survives
+//! under its own id for as long as anything still references it, including a
+//! [`keep_root`](RefCountedStore::keep_root)-pinned historical root. Content
+//! whose refcount reaches zero is queued and only physically reclaimed by
+//! [`prune`](RefCountedStore::prune), mirroring the HashDB kill/commit model.
+
+use crate::{
+    borrow::Cow,
+    codec::encode_branch_node,
+    collections::{BTreeMap, BTreeSet},
+    error::Result,
+    merge::MergeValue,
+    sha256::Sha256Hasher,
+    traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
+    tree::{BranchKey, BranchNode},
+    vec::Vec,
+    H256,
+};
+use core::marker::PhantomData;
+
+/// All content this store owns physically lives at this synthetic height, so
+/// a content id alone (32 bytes, collision-resistant) is enough to address it
+/// in `inner`; the real tree position is tracked separately in the index maps.
+const CONTENT_HEIGHT: u8 = 0;
+
+fn content_key(id: &H256) -> BranchKey {
+    BranchKey::new(CONTENT_HEIGHT, *id)
+}
+
+/// Hash a branch's `(left, right)` encoding into a content id. This is a plain
+/// digest for internal dedup/GC bookkeeping, deliberately independent of
+/// whichever `Hasher` the tree on top is configured with.
+fn branch_content_id(branch: &BranchNode) -> H256 {
+    let mut hasher = Sha256Hasher::default();
+    for byte in encode_branch_node(branch) {
+        hasher.write_byte(byte);
+    }
+    hasher.finish()
+}
+
+/// A store that refcounts its nodes *by content* and collects the
+/// unreferenced ones on demand.
+#[derive(Debug, Clone, Default)]
+pub struct RefCountedStore<V, S> {
+    inner: S,
+    // Tree position -> the content id currently stored there.
+    branch_index: BTreeMap<BranchKey, H256>,
+    leaf_index: BTreeMap<H256, H256>,
+    // Refcounts keyed by content id, shared across every position (and every
+    // historical version of a position) that happens to carry the same
+    // content.
+    branch_rc: BTreeMap<H256, u32>,
+    leaf_rc: BTreeMap<H256, u32>,
+    // Zero-count content awaiting physical removal at the next `prune`.
+    dead_branches: BTreeSet<H256>,
+    dead_leaves: BTreeSet<H256>,
+    // Content pinned live by a `keep_root` call; never collected while pinned.
+    pinned_branches: BTreeSet<H256>,
+    pinned_leaves: BTreeSet<H256>,
+    phantom: PhantomData<V>,
+}
+
+impl<V, S> RefCountedStore<V, S> {
+    /// Wrap `inner`, assuming it starts empty.
+    pub fn new(inner: S) -> Self {
+        RefCountedStore {
+            inner,
+            branch_index: BTreeMap::new(),
+            leaf_index: BTreeMap::new(),
+            branch_rc: BTreeMap::new(),
+            leaf_rc: BTreeMap::new(),
+            dead_branches: BTreeSet::new(),
+            dead_leaves: BTreeSet::new(),
+            pinned_branches: BTreeSet::new(),
+            pinned_leaves: BTreeSet::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Number of distinct branch contents still referenced by at least one
+    /// live position.
+    pub fn live_branches(&self) -> usize {
+        self.branch_rc.values().filter(|c| **c > 0).count()
+    }
+
+    /// Unwrap the backing store, discarding refcount bookkeeping.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn is_live_branch(&self, id: &H256) -> bool {
+        self.branch_rc.get(id).copied().unwrap_or(0) > 0
+    }
+
+    fn is_live_leaf(&self, id: &H256) -> bool {
+        self.leaf_rc.get(id).copied().unwrap_or(0) > 0
+    }
+
+    fn release_branch(&mut self, id: &H256) {
+        if let Some(count) = self.branch_rc.get_mut(id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.dead_branches.insert(*id);
+            }
+        }
+    }
+
+    fn release_leaf(&mut self, id: &H256) {
+        if let Some(count) = self.leaf_rc.get_mut(id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.dead_leaves.insert(*id);
+            }
+        }
+    }
+}
+
+impl<V: Clone, S: StoreWriteOps<V>> RefCountedStore<V, S> {
+    /// Physically remove every zero-count content no pinned root references.
+    pub fn prune(&mut self) -> Result<()> {
+        for id in core::mem::take(&mut self.dead_branches) {
+            if self.is_live_branch(&id) || self.pinned_branches.contains(&id) {
+                continue;
+            }
+            self.inner.remove_branch(&content_key(&id))?;
+            self.branch_rc.remove(&id);
+        }
+        for id in core::mem::take(&mut self.dead_leaves) {
+            if self.is_live_leaf(&id) || self.pinned_leaves.contains(&id) {
+                continue;
+            }
+            self.inner.remove_leaf(&id)?;
+            self.leaf_rc.remove(&id);
+        }
+        Ok(())
+    }
+
+    /// Apply all pending collections; synonym for [`prune`](RefCountedStore::prune).
+    pub fn commit(&mut self) -> Result<()> {
+        self.prune()
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V>> RefCountedStore<V, S> {
+    /// Pin every branch reachable from `root` (assumed to sit at
+    /// `root_height`, i.e. the owning tree's `max_height()`) plus the leaves
+    /// it plainly references, so that state survives later pruning even once
+    /// newer updates detach it from the live path. Walking from an explicit
+    /// root — rather than pinning everything currently live — is what lets
+    /// two historical roots coexist under GC.
+    ///
+    /// A child held as a [`MergeValue::MergeWithZero`] is followed like any
+    /// other non-zero child: `update` stores a branch at every height of a
+    /// key's path, so the branches below it and the leaves at height 0 are
+    /// pinned as well.
+    pub fn keep_root(&mut self, root: &H256, root_height: u8) -> Result<()> {
+        if root.is_zero() {
+            return Ok(());
+        }
+        let mut stack = Vec::new();
+        stack.push(BranchKey::new(root_height, H256::zero()));
+
+        while let Some(branch_key) = stack.pop() {
+            let content_id = match self.branch_index.get(&branch_key) {
+                Some(id) => *id,
+                None => continue,
+            };
+            if !self.pinned_branches.insert(content_id) {
+                continue; // already pinned, and so is everything below it
+            }
+            let node = match self.get_branch(&branch_key)? {
+                Some(node) => node.into_owned(),
+                None => continue,
+            };
+
+            let left_key = branch_key.node_key;
+            let mut right_key = branch_key.node_key;
+            right_key.set_bit(branch_key.height);
+
+            if branch_key.height == 0 {
+                for (child_value, leaf_key) in [(&node.left, left_key), (&node.right, right_key)] {
+                    if child_value.is_zero() {
+                        continue;
+                    }
+                    if let Some(leaf_id) = self.leaf_index.get(&leaf_key) {
+                        self.pinned_leaves.insert(*leaf_id);
+                    }
+                }
+            } else {
+                let child_height = branch_key.height - 1;
+                for (child_value, child_key) in [(&node.left, left_key), (&node.right, right_key)] {
+                    if child_value.is_zero() {
+                        continue;
+                    }
+                    stack.push(BranchKey::new(child_height, child_key));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V: Clone, S: StoreReadOps<V>> StoreReadOps<V> for RefCountedStore<V, S> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        match self.branch_index.get(key) {
+            Some(id) => self.inner.get_branch(&content_key(id)),
+            None => Ok(None),
+        }
+    }
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        match self.leaf_index.get(key) {
+            Some(id) => self.inner.get_leaf(id),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<V: Value, S: StoreWriteOps<V>> StoreWriteOps<V> for RefCountedStore<V, S> {
+    fn insert_branch(&mut self, key: BranchKey, branch: BranchNode) -> Result<()> {
+        let content_id = branch_content_id(&branch);
+        // Write the content once per id; a second writer of identical content
+        // shares the existing entry instead of rewriting it.
+        if self.branch_rc.get(&content_id).copied().unwrap_or(0) == 0 {
+            self.inner.insert_branch(content_key(&content_id), branch)?;
+        }
+        let count = self.branch_rc.entry(content_id).or_insert(0);
+        *count = count.saturating_add(1);
+        self.dead_branches.remove(&content_id);
+
+        // Repointing this position drops its previous content's reference
+        // instead of destroying it, so the old content survives under its own
+        // id for as long as anything (e.g. a `keep_root` pin) needs it.
+        if let Some(old_id) = self.branch_index.insert(key, content_id) {
+            if old_id != content_id {
+                self.release_branch(&old_id);
+            }
+        }
+        Ok(())
+    }
+    fn insert_leaf(&mut self, key: H256, leaf: V) -> Result<()> {
+        let content_id = leaf.to_h256();
+        if self.leaf_rc.get(&content_id).copied().unwrap_or(0) == 0 {
+            self.inner.insert_leaf(content_id, leaf)?;
+        }
+        let count = self.leaf_rc.entry(content_id).or_insert(0);
+        *count = count.saturating_add(1);
+        self.dead_leaves.remove(&content_id);
+
+        if let Some(old_id) = self.leaf_index.insert(key, content_id) {
+            if old_id != content_id {
+                self.release_leaf(&old_id);
+            }
+        }
+        Ok(())
+    }
+    fn remove_branch(&mut self, key: &BranchKey) -> Result<()> {
+        if let Some(id) = self.branch_index.remove(key) {
+            self.release_branch(&id);
+        }
+        Ok(())
+    }
+    fn remove_leaf(&mut self, key: &H256) -> Result<()> {
+        if let Some(id) = self.leaf_index.remove(key) {
+            self.release_leaf(&id);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+//! Zero-copy archival of proofs and nodes with [`rkyv`].
+//!
+//! Gated behind the `rkyv` feature. The crate's own node types hold a foreign
+//! `H256`, so archival goes through plain `[u8; 32]`-backed mirror types that
+//! derive rkyv's traits. [`ArchivedProof::verify`] does not run on the archived
+//! bytes in place: it first rebuilds an owned [`MerkleProof`] from the mirror,
+//! which allocates, and verifies that.
+
+#![cfg(feature = "rkyv")]
+
+use crate::{
+    error::Result,
+    merge::MergeValue,
+    merkle_proof::MerkleProof,
+    traits::Hasher,
+    vec::Vec,
+    H256,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Archival mirror of a [`MergeValue`].
+#[derive(Archive, Serialize, Deserialize, Debug, Clone)]
+pub enum ArchivedMergeValue {
+    Value([u8; 32]),
+    MergeWithZero {
+        base_node: [u8; 32],
+        zero_bits: [u8; 32],
+        zero_count: u8,
+        value: [u8; 32],
+    },
+}
+
+impl From<&MergeValue> for ArchivedMergeValue {
+    fn from(value: &MergeValue) -> Self {
+        match value {
+            MergeValue::Value(v) => ArchivedMergeValue::Value(to_bytes(v)),
+            MergeValue::MergeWithZero {
+                base_node,
+                zero_bits,
+                zero_count,
+                value,
+            } => ArchivedMergeValue::MergeWithZero {
+                base_node: to_bytes(base_node),
+                zero_bits: to_bytes(zero_bits),
+                zero_count: *zero_count,
+                value: to_bytes(value),
+            },
+        }
+    }
+}
+
+impl From<&ArchivedMergeValue> for MergeValue {
+    fn from(value: &ArchivedMergeValue) -> Self {
+        match value {
+            ArchivedMergeValue::Value(v) => MergeValue::from_h256((*v).into()),
+            ArchivedMergeValue::MergeWithZero {
+                base_node,
+                zero_bits,
+                zero_count,
+                value,
+            } => MergeValue::MergeWithZero {
+                base_node: (*base_node).into(),
+                zero_bits: (*zero_bits).into(),
+                zero_count: *zero_count,
+                value: (*value).into(),
+            },
+        }
+    }
+}
+
+/// Archival mirror of a [`MerkleProof`].
+#[derive(Archive, Serialize, Deserialize, Debug, Clone)]
+pub struct ArchivedProof {
+    pub leaves_bitmap: Vec<[u8; 32]>,
+    pub merkle_path: Vec<ArchivedMergeValue>,
+}
+
+impl From<&MerkleProof> for ArchivedProof {
+    fn from(proof: &MerkleProof) -> Self {
+        ArchivedProof {
+            leaves_bitmap: proof.leaves_bitmap().iter().map(to_bytes).collect(),
+            merkle_path: proof.merkle_path().iter().map(ArchivedMergeValue::from).collect(),
+        }
+    }
+}
+
+impl ArchivedProof {
+    /// Reconstruct a live [`MerkleProof`] from the archived form.
+    pub fn to_proof(&self) -> MerkleProof {
+        let leaves_bitmap = self.leaves_bitmap.iter().map(|b| (*b).into()).collect();
+        let merkle_path = self.merkle_path.iter().map(MergeValue::from).collect();
+        MerkleProof::new(leaves_bitmap, merkle_path)
+    }
+
+    /// Verify the archived proof against `root` by rebuilding an owned
+    /// [`MerkleProof`] with [`to_proof`](Self::to_proof) and verifying that.
+    pub fn verify<H: Hasher + Default>(
+        &self,
+        root: &H256,
+        leaves: Vec<(H256, H256)>,
+    ) -> Result<bool> {
+        self.to_proof().verify::<H>(root, leaves)
+    }
+}
+
+fn to_bytes(h: &H256) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(h.as_slice());
+    out
+}
@@ -136,3 +136,71 @@ proptest! {
         }
     }
 }
+
+#[test]
+fn test_verify_with_options_mixed_present_and_absent() {
+    // A single proof attests both a present key and an explicitly-absent one.
+    let present = H256::from([1u8; 32]);
+    let value = H256::from([2u8; 32]);
+    let absent = H256::from([3u8; 32]);
+
+    let mut tree = CkbSMT::default();
+    tree.update(present, value).expect("update");
+
+    let proof = tree
+        .merkle_proof(vec![present, absent])
+        .expect("proof over present and absent keys");
+
+    // `Some(value)` proves membership, `None` proves the absent key sits in
+    // empty subtree territory covered by the same proof.
+    assert!(proof
+        .clone()
+        .verify_with_options::<CkbBlake2bHasher>(
+            tree.root(),
+            vec![(present, Some(value)), (absent, None)],
+        )
+        .expect("verify with options"));
+
+    // Claiming the absent key is present must not verify against the root.
+    assert!(!proof
+        .verify_with_options::<CkbBlake2bHasher>(
+            tree.root(),
+            vec![(present, Some(value)), (absent, Some(value))],
+        )
+        .expect("verify with options"));
+}
+
+#[test]
+fn test_verify_with_assertions_rejects_tombstone_membership() {
+    use sparse_merkle_tree::error::Error;
+
+    let present = H256::from([1u8; 32]);
+    let value = H256::from([2u8; 32]);
+    let absent = H256::from([3u8; 32]);
+
+    let mut tree = CkbSMT::default();
+    tree.update(present, value).expect("update");
+
+    let proof = tree
+        .merkle_proof(vec![present, absent])
+        .expect("proof over present and absent keys");
+
+    // A present key plus an absent assertion verifies as membership/non-membership.
+    assert!(proof
+        .clone()
+        .verify_with_assertions::<CkbBlake2bHasher>(
+            tree.root(),
+            vec![(present, Some(value)), (absent, None)],
+        )
+        .expect("verify assertions"));
+
+    // Asserting membership with the zero value claims a live leaf over a
+    // tombstone and is rejected outright rather than silently treated as absent.
+    assert_eq!(
+        proof.verify_with_assertions::<CkbBlake2bHasher>(
+            tree.root(),
+            vec![(present, Some(value)), (absent, Some(H256::zero()))],
+        ),
+        Err(Error::CorruptedProof)
+    );
+}
@@ -105,6 +105,124 @@ fn test_merkle_root() {
     assert_eq!(tree.root(), &expected_root);
 }
 
+#[cfg(feature = "blake3")]
+#[test]
+fn test_merkle_root_blake3() {
+    use crate::blake3_hasher::{Blake3Hasher, Blake3Smt};
+
+    fn new_blake2b() -> crate::blake2b::Blake2b {
+        crate::blake2b::Blake2bBuilder::new(32)
+            .personal(b"SMT")
+            .build()
+    }
+
+    // Same key/value material as `test_merkle_root`, folded with Blake3 instead.
+    let pairs: Vec<(H256, H256)> = "The quick brown fox jumps over the lazy dog"
+        .split_whitespace()
+        .enumerate()
+        .map(|(i, word)| {
+            let key: H256 = {
+                let mut buf = [0u8; 32];
+                let mut hasher = new_blake2b();
+                hasher.update(&(i as u32).to_le_bytes());
+                hasher.finalize(&mut buf);
+                buf.into()
+            };
+            let value: H256 = {
+                let mut buf = [0u8; 32];
+                let mut hasher = new_blake2b();
+                hasher.update(word.as_bytes());
+                hasher.finalize(&mut buf);
+                buf.into()
+            };
+            (key, value)
+        })
+        .collect();
+
+    let mut blake3_tree = Blake3Smt::<H256>::default();
+    let mut blake2b_tree = SMT::default();
+    for (key, value) in &pairs {
+        blake3_tree.update(*key, *value).expect("update");
+        blake2b_tree.update(*key, *value).expect("update");
+    }
+    assert_eq!(blake3_tree.store().leaves_map().len(), 9);
+
+    // Swapping the hasher yields a different, non-empty root.
+    assert_ne!(blake3_tree.root(), &H256::zero());
+    assert_ne!(blake3_tree.root(), blake2b_tree.root());
+
+    // The Blake3 root is deterministic: rebuilding the same leaves through the
+    // batched `update_all` path must fold to the exact same root as the
+    // sequential `update` path above, not merely "some non-zero value".
+    let mut blake3_tree_batched = Blake3Smt::<H256>::default();
+    blake3_tree_batched
+        .update_all(pairs.clone())
+        .expect("update_all");
+    assert_eq!(blake3_tree_batched.root(), blake3_tree.root());
+
+    // `compute_root`/proof compilation are generic over the hasher, so a compiled
+    // multi-proof verifies identically under Blake3.
+    let keys: Vec<H256> = pairs.iter().map(|(k, _)| *k).collect();
+    let proof = blake3_tree.merkle_proof(keys.clone()).expect("proof");
+    let compiled = proof.clone().compile(keys).expect("compile proof");
+    assert!(proof
+        .verify::<Blake3Hasher>(blake3_tree.root(), pairs.clone())
+        .expect("verify"));
+    assert!(compiled
+        .verify::<Blake3Hasher>(blake3_tree.root(), pairs.clone())
+        .expect("verify compiled"));
+
+    // `extract_proof` is generic over the hasher too: a sub-proof carved out for
+    // half the keys must verify identically under Blake3.
+    let selected: Vec<(H256, H256)> = pairs.iter().step_by(2).cloned().collect();
+    let membership: Vec<(H256, H256, bool)> = pairs
+        .iter()
+        .map(|(k, v)| (*k, *v, selected.iter().any(|(sk, _)| sk == k)))
+        .collect();
+    let sub_proof = compiled
+        .extract_proof::<Blake3Hasher>(membership)
+        .expect("extract sub proof");
+    assert!(sub_proof
+        .verify::<Blake3Hasher>(blake3_tree.root(), selected)
+        .expect("verify sub proof"));
+}
+
+#[cfg(feature = "blake3")]
+#[test]
+fn test_algorithm_tagged_proof_round_trip() {
+    use crate::blake3_hasher::{Blake3Hasher, Blake3Smt};
+    use crate::codec::{decode_merkle_proof_with_algorithm, encode_merkle_proof_with_algorithm};
+    use crate::hash_algorithm::IdentifiedHasher;
+
+    // One correctness suite shared by both backends: build a proof, round-trip
+    // it through its algorithm-tagged header, and confirm it still verifies.
+    fn round_trip<H: crate::traits::Hasher + Default + IdentifiedHasher>() {
+        let key = H256::from([7u8; 32]);
+        let value = H256::from([9u8; 32]);
+        let mut tree = SparseMerkleTree::<H, H256, DefaultStore<H256>>::default();
+        tree.update(key, value).expect("update");
+
+        let proof = tree.merkle_proof(vec![key]).expect("proof");
+        let encoded = encode_merkle_proof_with_algorithm::<H>(&proof);
+        let decoded = decode_merkle_proof_with_algorithm::<H>(&encoded).expect("decode");
+        assert_eq!(decoded, proof);
+        assert!(decoded
+            .verify::<H>(tree.root(), vec![(key, value)])
+            .expect("verify"));
+    }
+
+    round_trip::<Blake2bHasher>();
+    round_trip::<Blake3Hasher>();
+
+    // A proof tagged with one algorithm must not decode under the other.
+    let key = H256::from([1u8; 32]);
+    let mut tree = Blake3Smt::<H256>::default();
+    tree.update(key, H256::from([2u8; 32])).expect("update");
+    let encoded =
+        encode_merkle_proof_with_algorithm::<Blake3Hasher>(&tree.merkle_proof(vec![key]).unwrap());
+    assert!(decode_merkle_proof_with_algorithm::<Blake2bHasher>(&encoded).is_err());
+}
+
 #[test]
 fn test_zero_value_donot_change_root() {
     let mut tree = SMT::default();
@@ -1172,3 +1290,1031 @@ fn test_sub_proof(
             .expect("verify compiled sub proof"));
     }
 }
+
+#[test]
+fn test_circuit_witness_matches_root() {
+    // A fixed-depth circuit witness must fold back to exactly `tree.root()`,
+    // both for a present key and for an absent key (whose leaf hash is zero).
+    let keys: Vec<H256> = (0u8..16)
+        .map(|i| {
+            let mut buf = [0u8; 32];
+            buf[0] = i;
+            buf[31] = i.wrapping_mul(7);
+            H256::from(buf)
+        })
+        .collect();
+    let mut tree = SMT::default();
+    for (i, key) in keys.iter().enumerate() {
+        tree.update(*key, H256::from([(i as u8).wrapping_add(1); 32]))
+            .expect("update");
+    }
+
+    for key in &keys {
+        let witness = tree.circuit_witness(*key).expect("witness");
+        assert_eq!(&witness.compute_root::<Blake2bHasher>(), tree.root());
+    }
+
+    // A key that was never inserted still produces a witness folding to the root.
+    let absent = H256::from([0xaa; 32]);
+    let witness = tree.circuit_witness(absent).expect("witness");
+    assert_eq!(&witness.compute_root::<Blake2bHasher>(), tree.root());
+}
+
+#[test]
+fn test_range_proof_empty_interval() {
+    use crate::range_proof::{BoundaryLeaf, KeyRange, RangeProof};
+
+    fn key_at(top: u8) -> H256 {
+        let mut buf = [0u8; 32];
+        buf[0] = top;
+        H256::from(buf)
+    }
+    let val = H256::from([1u8; 32]);
+
+    let left_key = key_at(0x10);
+    let right_key = key_at(0x30);
+    let mut tree = SMT::default();
+    tree.update(left_key, val).expect("update");
+    tree.update(right_key, val).expect("update");
+
+    let boundary = |tree: &SMT, key: H256| {
+        BoundaryLeaf::new(key, tree.get(&key).unwrap(), tree.merkle_path(key).unwrap())
+    };
+
+    // The open gap (0x10, 0x30) is genuinely empty, so the proof verifies.
+    let range = KeyRange::new(Some(key_at(0x18)), Some(key_at(0x28)));
+    let proof = RangeProof::new(
+        Some(boundary(&tree, left_key)),
+        Some(boundary(&tree, right_key)),
+    );
+    assert!(proof.verify::<Blake2bHasher>(tree.root(), &range).unwrap());
+}
+
+#[test]
+fn test_range_proof_rejects_mid_interval_key() {
+    use crate::range_proof::{BoundaryLeaf, KeyRange, RangeProof};
+
+    fn key_at(top: u8) -> H256 {
+        let mut buf = [0u8; 32];
+        buf[0] = top;
+        H256::from(buf)
+    }
+    let val = H256::from([1u8; 32]);
+
+    let left_key = key_at(0x10);
+    let mid_key = key_at(0x20);
+    let right_key = key_at(0x30);
+    let mut tree = SMT::default();
+    tree.update(left_key, val).expect("update");
+    tree.update(mid_key, val).expect("update");
+    tree.update(right_key, val).expect("update");
+
+    let boundary = |tree: &SMT, key: H256| {
+        BoundaryLeaf::new(key, tree.get(&key).unwrap(), tree.merkle_path(key).unwrap())
+    };
+
+    // A key sits inside (0x10, 0x30): the gap-facing sibling is non-zero, so the
+    // boundary openings — though each verifies as a member — must not prove the
+    // interval empty. This is the soundness regression the rewrite closes.
+    let range = KeyRange::new(Some(key_at(0x18)), Some(key_at(0x28)));
+    let proof = RangeProof::new(
+        Some(boundary(&tree, left_key)),
+        Some(boundary(&tree, right_key)),
+    );
+    assert!(!proof.verify::<Blake2bHasher>(tree.root(), &range).unwrap());
+}
+
+#[test]
+fn test_range_proof_open_lower_bound() {
+    use crate::range_proof::{BoundaryLeaf, KeyRange, RangeProof};
+
+    fn key_at(top: u8) -> H256 {
+        let mut buf = [0u8; 32];
+        buf[0] = top;
+        H256::from(buf)
+    }
+    let val = H256::from([1u8; 32]);
+
+    let min_key = key_at(0x20);
+    let mut tree = SMT::default();
+    tree.update(min_key, val).expect("update");
+    tree.update(key_at(0x40), val).expect("update");
+
+    let boundary = |tree: &SMT, key: H256| {
+        BoundaryLeaf::new(key, tree.get(&key).unwrap(), tree.merkle_path(key).unwrap())
+    };
+
+    // Nothing exists below the global minimum 0x20, so (-inf, 0x20) is empty.
+    let range = KeyRange::new(None, Some(key_at(0x10)));
+    let proof = RangeProof::new(None, Some(boundary(&tree, min_key)));
+    assert!(proof.verify::<Blake2bHasher>(tree.root(), &range).unwrap());
+
+    // Insert a smaller key: 0x20 is no longer the minimum and the proof fails.
+    tree.update(key_at(0x05), val).expect("update");
+    let proof = RangeProof::new(None, Some(boundary(&tree, min_key)));
+    assert!(!proof.verify::<Blake2bHasher>(tree.root(), &range).unwrap());
+}
+
+#[test]
+fn test_partitioned_proof_round_trip() {
+    use crate::partitioned::PartitionedSMT;
+
+    let mut tree = PartitionedSMT::<Blake2bHasher, H256>::new(3);
+    let pairs: Vec<(H256, H256)> = (0u8..24)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(11);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    for (key, value) in &pairs {
+        tree.update(*key, *value).expect("update");
+    }
+
+    for (key, value) in &pairs {
+        let root = *tree.root();
+        let proof = tree.merkle_proof(*key).expect("proof");
+        assert!(proof
+            .verify::<Blake2bHasher>(&root, *key, *value)
+            .expect("verify"));
+
+        // A wrong value must not verify against the global root.
+        let root = *tree.root();
+        let proof = tree.merkle_proof(*key).expect("proof");
+        assert!(!proof
+            .verify::<Blake2bHasher>(&root, *key, H256::from([0xff; 32]))
+            .expect("verify"));
+    }
+}
+
+#[test]
+fn test_new_with_height_restricts_branches_and_rejects_out_of_range_keys() {
+    let mut tree = SMT::new_with_height(H256::zero(), DefaultStore::default(), 16);
+    assert_eq!(tree.max_height(), 15);
+
+    let key = H256::zero();
+    let mut key2_bytes = [0u8; 32];
+    key2_bytes[31] = 7;
+    let key2 = H256::from(key2_bytes);
+
+    tree.update(key, [1u8; 32].into()).expect("update");
+    tree.update(key2, [2u8; 32].into()).expect("update");
+
+    // Only heights 0..=15 are ever walked, so at most 16 branches can exist.
+    assert!(tree.store().branches_map().len() <= 16);
+
+    let path = tree.merkle_path(key2).expect("path");
+    assert!(path.verify::<Blake2bHasher>(tree.root(), key2, [2u8; 32].into()));
+
+    // A key with a bit set above height 15 (outside the first two bytes) is
+    // out of range for this tree and must be rejected, not silently truncated.
+    let mut bad_key_bytes = [0u8; 32];
+    bad_key_bytes[31] = 1;
+    let bad_key = H256::from(bad_key_bytes);
+    assert!(matches!(
+        tree.update(bad_key, [3u8; 32].into()),
+        Err(Error::Store(_))
+    ));
+    assert!(matches!(tree.merkle_path(bad_key), Err(Error::Store(_))));
+}
+
+#[test]
+fn test_merkle_path_membership_and_absence() {
+    let pairs: Vec<(H256, H256)> = (0u8..20)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(13);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    let mut tree = SMT::default();
+    for (key, value) in &pairs {
+        tree.update(*key, *value).expect("update");
+    }
+
+    // Every present key folds back to the root, and a wrong value does not.
+    for (key, value) in &pairs {
+        let path = tree.merkle_path(*key).expect("path");
+        assert!(path.verify::<Blake2bHasher>(tree.root(), *key, *value));
+        assert!(!path.verify::<Blake2bHasher>(tree.root(), *key, H256::from([0xee; 32])));
+    }
+
+    // An absent key proves non-inclusion: its zero value folds to the root,
+    // while any non-zero value does not.
+    let absent = H256::from([0x7f; 32]);
+    let path = tree.merkle_path(absent).expect("path");
+    assert!(path.verify::<Blake2bHasher>(tree.root(), absent, H256::zero()));
+    assert!(!path.verify::<Blake2bHasher>(tree.root(), absent, H256::from([1u8; 32])));
+}
+
+#[test]
+fn test_codec_merkle_proof_round_trip() {
+    use crate::codec::{decode_merkle_proof, encode_merkle_proof};
+
+    let pairs: Vec<(H256, H256)> = (0u8..12)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(17);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    let mut tree = SMT::default();
+    for (key, value) in &pairs {
+        tree.update(*key, *value).expect("update");
+    }
+
+    let keys: Vec<H256> = pairs.iter().map(|(k, _)| *k).collect();
+    let proof = tree.merkle_proof(keys).expect("proof");
+
+    // The wire encoding must round-trip to an identical proof that still verifies.
+    let bytes = encode_merkle_proof(&proof);
+    let decoded = decode_merkle_proof(&bytes).expect("decode");
+    assert_eq!(decoded, proof);
+    assert!(decoded
+        .verify::<Blake2bHasher>(tree.root(), pairs)
+        .expect("verify"));
+}
+
+#[test]
+fn test_decode_merkle_proof_rejects_oversized_length_prefixes() {
+    use crate::codec::{decode_merkle_proof, VERSION};
+
+    // A `bitmap_count` of u32::MAX claims ~128 GiB of bitmaps from 5 trailing
+    // bytes; this must fail cleanly as corrupted rather than attempt the
+    // allocation implied by the untrusted count.
+    let mut huge_bitmap_count = crate::vec![VERSION];
+    huge_bitmap_count.extend_from_slice(&u32::MAX.to_le_bytes());
+    huge_bitmap_count.extend_from_slice(&[0u8; 4]);
+    assert!(matches!(
+        decode_merkle_proof(&huge_bitmap_count),
+        Err(Error::CorruptedProof)
+    ));
+
+    // Same for `path_count`, given a single (valid) bitmap entry first.
+    let mut huge_path_count = crate::vec![VERSION];
+    huge_path_count.extend_from_slice(&1u32.to_le_bytes());
+    huge_path_count.extend_from_slice(&[0u8; 32]);
+    huge_path_count.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(
+        decode_merkle_proof(&huge_path_count),
+        Err(Error::CorruptedProof)
+    ));
+}
+
+#[test]
+fn test_compiled_merkle_proof_merge_round_trip() {
+    let pairs: Vec<(H256, H256)> = (0u8..20)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(11);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    let smt = new_smt(pairs.clone());
+
+    let (first, second) = pairs.split_at(pairs.len() / 2);
+    let first_proof = smt
+        .merkle_proof(first.iter().map(|(k, _)| *k).collect())
+        .expect("gen proof")
+        .compile(first.iter().map(|(k, _)| *k).collect())
+        .expect("compile");
+    let second_proof = smt
+        .merkle_proof(second.iter().map(|(k, _)| *k).collect())
+        .expect("gen proof")
+        .compile(second.iter().map(|(k, _)| *k).collect())
+        .expect("compile");
+
+    let merged = CompiledMerkleProof::merge::<Blake2bHasher>(vec![
+        (first_proof, first.to_vec()),
+        (second_proof, second.to_vec()),
+    ])
+    .expect("merge");
+
+    // The fused proof verifies every leaf from both inputs against the same root.
+    assert!(merged
+        .verify::<Blake2bHasher>(smt.root(), pairs.clone())
+        .expect("verify merged"));
+
+    // It should equal the proof generated directly over the union of keys.
+    let keys: Vec<H256> = pairs.iter().map(|(k, _)| *k).collect();
+    let direct = smt
+        .merkle_proof(keys.clone())
+        .expect("gen proof")
+        .compile(keys)
+        .expect("compile");
+    assert_eq!(merged.0, direct.0);
+}
+
+#[test]
+fn test_compiled_merkle_proof_merge_rejects_conflicting_siblings() {
+    let pairs: Vec<(H256, H256)> = (0u8..8)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(11);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    let smt = new_smt(pairs.clone());
+
+    // Build two trees with the same key but different sibling contents, then
+    // a proof covering that key from each: the recorded siblings disagree.
+    let mut other_pairs = pairs.clone();
+    other_pairs[0].1 = H256::from([0xaa; 32]);
+    let other_smt = new_smt(other_pairs.clone());
+
+    let key = pairs[1].0;
+    let proof_a = smt
+        .merkle_proof(vec![key])
+        .expect("gen proof")
+        .compile(vec![key])
+        .expect("compile");
+    let proof_b = other_smt
+        .merkle_proof(vec![key])
+        .expect("gen proof")
+        .compile(vec![key])
+        .expect("compile");
+
+    let result = CompiledMerkleProof::merge::<Blake2bHasher>(vec![
+        (proof_a, vec![(key, pairs[1].1)]),
+        (proof_b, vec![(key, other_pairs[1].1)]),
+    ]);
+    assert_eq!(result.unwrap_err(), Error::CorruptedProof);
+}
+
+#[test]
+fn test_compiled_merkle_proof_validate_accepts_balanced_program() {
+    let pairs: Vec<(H256, H256)> = (0u8..6)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(23);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    let smt = new_smt(pairs.clone());
+    let keys: Vec<H256> = pairs.iter().map(|(k, _)| *k).collect();
+    let compiled = smt
+        .merkle_proof(keys.clone())
+        .expect("gen proof")
+        .compile(keys)
+        .expect("compile");
+
+    compiled.validate().expect("well-formed program validates");
+    let ops = compiled.disassemble().expect("disassemble");
+    assert!(!ops.is_empty());
+    assert!(matches!(ops[0], ProofOp::PushLeaf));
+}
+
+#[test]
+fn test_compiled_merkle_proof_validate_rejects_malformed_programs() {
+    // Dangling `P` (0x50): nothing on the stack to merge.
+    let dangling_p = CompiledMerkleProof([vec![0x50], vec![0u8; 32]].concat());
+    assert_eq!(dangling_p.validate().unwrap_err(), Error::CorruptedStack);
+
+    // Dangling `Q` (0x51): same, no pushed leaf to merge with.
+    let dangling_q = CompiledMerkleProof([vec![0x51], vec![0u8; 65]].concat());
+    assert_eq!(dangling_q.validate().unwrap_err(), Error::CorruptedStack);
+
+    // Dangling `O` (0x4F): no pushed leaf to fold zeros into.
+    let dangling_o = CompiledMerkleProof(vec![0x4F, 1]);
+    assert_eq!(dangling_o.validate().unwrap_err(), Error::CorruptedStack);
+
+    // Mismatched-height `H` (0x48): two leaves, one already merged once.
+    let mismatched_h = CompiledMerkleProof(
+        [vec![0x4C], vec![0x4C], vec![0x50], vec![0u8; 32], vec![0x48]].concat(),
+    );
+    assert_eq!(mismatched_h.validate().unwrap_err(), Error::CorruptedProof);
+
+    // Zero-run overflow: pushed leaf is already at height 256 worth of folding.
+    let mut overflow = vec![0x4C, 0x4F, 0];
+    // n == 0 means 256, folding a leaf (height 0) by 256 reaches exactly 256 -
+    // that alone is valid, so push it a second time to overflow past 256.
+    overflow.extend_from_slice(&[0x4F, 0]);
+    assert_eq!(
+        CompiledMerkleProof(overflow).validate().unwrap_err(),
+        Error::CorruptedProof
+    );
+
+    // Trailing bytes: a `P` whose 32-byte sibling is truncated.
+    let short_p = CompiledMerkleProof([vec![0x4C], vec![0x50], vec![0u8; 10]].concat());
+    assert_eq!(short_p.validate().unwrap_err(), Error::CorruptedProof);
+
+    // Unknown opcode.
+    let bad_code = CompiledMerkleProof(vec![0xFF]);
+    assert_eq!(bad_code.validate().unwrap_err(), Error::InvalidCode(0xFF));
+}
+
+#[test]
+fn test_compiled_merkle_proof_transition() {
+    let pairs: Vec<(H256, H256)> = (0u8..10)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(17);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    let mut tree = new_smt(pairs.clone());
+    let old_root = *tree.root();
+
+    let updated: Vec<(H256, H256)> = pairs
+        .iter()
+        .take(3)
+        .map(|(k, v)| (*k, H256::from([v.as_slice()[0].wrapping_add(0x10); 32])))
+        .collect();
+    let keys: Vec<H256> = pairs.iter().map(|(k, _)| *k).collect();
+    let proof = tree
+        .merkle_proof(keys.clone())
+        .expect("gen proof")
+        .compile(keys)
+        .expect("compile");
+
+    let updates: Vec<(H256, H256, H256)> = updated
+        .iter()
+        .map(|(k, new_v)| {
+            let old_v = pairs.iter().find(|(pk, _)| pk == k).unwrap().1;
+            (*k, old_v, *new_v)
+        })
+        .collect();
+
+    let new_root = proof
+        .transition::<Blake2bHasher>(&old_root, updates.clone())
+        .expect("transition");
+
+    for (key, new_value) in &updated {
+        tree.update(*key, *new_value).expect("update");
+    }
+    assert_eq!(&new_root, tree.root());
+
+    // A tampered old_value means the proof no longer folds to old_root.
+    let mut tampered_updates = updates;
+    tampered_updates[0].1 = H256::from([0xee; 32]);
+    assert_eq!(
+        proof
+            .transition::<Blake2bHasher>(&old_root, tampered_updates)
+            .unwrap_err(),
+        Error::CorruptedProof
+    );
+}
+
+#[test]
+fn test_verify_membership_statuses() {
+    use crate::merkle_proof::MembershipStatus;
+
+    let pairs: Vec<(H256, H256)> = (0u8..10)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(19);
+            key[31] = i;
+            (H256::from(key), H256::from([i.wrapping_add(1); 32]))
+        })
+        .collect();
+    let smt = new_smt(pairs.clone());
+
+    let present_key = pairs[0].0;
+    let present_value = pairs[0].1;
+    let absent_key = H256::from([0x7f; 32]);
+
+    let queries = vec![
+        (present_key, Some(present_value)),
+        (absent_key, None),
+    ];
+    let keys: Vec<H256> = queries.iter().map(|(k, _)| *k).collect();
+    let proof = smt.merkle_proof(keys).expect("gen proof");
+
+    let statuses = proof
+        .clone()
+        .verify_membership::<Blake2bHasher>(smt.root(), queries.clone())
+        .expect("verify membership");
+    assert_eq!(
+        statuses,
+        vec![
+            MembershipStatus::Included(present_value),
+            MembershipStatus::Excluded,
+        ]
+    );
+
+    // A wrong claimed value against the same root reports Mismatch for every query.
+    let wrong_queries = vec![
+        (present_key, Some(H256::from([0xaa; 32]))),
+        (absent_key, None),
+    ];
+    let proof2 = smt
+        .merkle_proof(wrong_queries.iter().map(|(k, _)| *k).collect())
+        .expect("gen proof");
+    let statuses = proof2
+        .verify_membership::<Blake2bHasher>(smt.root(), wrong_queries)
+        .expect("verify membership");
+    assert_eq!(
+        statuses,
+        vec![MembershipStatus::Mismatch, MembershipStatus::Mismatch]
+    );
+}
+
+#[test]
+fn test_overlay_store_stages_then_commits_or_discards() {
+    use crate::overlay_store::OverlayStore;
+
+    let base_key = H256::from([1u8; 32]);
+    let base_value = H256::from([2u8; 32]);
+    let mut base = new_smt(vec![(base_key, base_value)]);
+    let base_root = *base.root();
+
+    // Staged writes are visible through the overlay but not in the under store.
+    let mut overlay_tree = SparseMerkleTree::<Blake2bHasher, H256, _>::new(
+        base_root,
+        OverlayStore::new(base.store_mut().clone()),
+    );
+    let staged_key = H256::from([3u8; 32]);
+    let staged_value = H256::from([4u8; 32]);
+    overlay_tree
+        .update(staged_key, staged_value)
+        .expect("stage update");
+    assert_eq!(
+        overlay_tree.get(&staged_key).expect("get staged"),
+        staged_value
+    );
+    assert_ne!(overlay_tree.root(), &base_root);
+
+    let under_only = SMT::new(base_root, base.store().clone());
+    assert_eq!(
+        under_only.get(&staged_key).expect("get from under"),
+        H256::zero()
+    );
+
+    let new_root = *overlay_tree.root();
+    overlay_tree.store_mut().commit().expect("commit overlay");
+    let committed_store = overlay_tree.take_store().into_inner();
+    let committed_tree = SMT::new(new_root, committed_store);
+    assert_eq!(
+        committed_tree.get(&staged_key).expect("get after commit"),
+        staged_value
+    );
+
+    // A second staging round that is discarded must leave the under store as-is.
+    let mut discard_tree = SparseMerkleTree::<Blake2bHasher, H256, _>::new(
+        new_root,
+        OverlayStore::new(committed_tree.store().clone()),
+    );
+    let other_key = H256::from([5u8; 32]);
+    discard_tree
+        .update(other_key, H256::from([6u8; 32]))
+        .expect("stage another update");
+    discard_tree.store_mut().discard();
+    let untouched_store = discard_tree.take_store().into_inner();
+    let untouched_tree = SMT::new(new_root, untouched_store);
+    assert_eq!(
+        untouched_tree.get(&other_key).expect("get after discard"),
+        H256::zero()
+    );
+    assert_eq!(untouched_tree.root(), &new_root);
+}
+
+#[test]
+fn test_lazy_smt_flush_matches_eager_update() {
+    use crate::lazy::LazySmt;
+
+    let pairs: Vec<(H256, H256)> = (0u8..10)
+        .map(|i| (H256::from([i; 32]), H256::from([i.wrapping_add(1); 32])))
+        .collect();
+
+    let mut lazy = LazySmt::new(SMT::default());
+    assert_eq!(lazy.dirty_len(), 0);
+    for (key, value) in &pairs {
+        lazy.update(*key, *value);
+    }
+    // Nothing is recomputed until `flush`: the root is still the empty one,
+    // but every key is queued.
+    assert_eq!(lazy.dirty_len(), pairs.len());
+    assert_eq!(lazy.root(), &H256::zero());
+
+    let flushed_root = *lazy.flush().expect("flush");
+    assert_eq!(lazy.dirty_len(), 0);
+
+    let eager = new_smt(pairs);
+    assert_eq!(&flushed_root, eager.root());
+
+    // A second, empty flush is a no-op and keeps the same root.
+    assert_eq!(lazy.flush().expect("flush again"), &flushed_root);
+}
+
+#[test]
+fn test_sync_diff_finds_only_divergent_keys() {
+    use crate::sync::{diff, Diff};
+
+    let shared: Vec<(H256, H256)> = (0u8..10)
+        .map(|i| (H256::from([i; 32]), H256::from([i.wrapping_add(1); 32])))
+        .collect();
+
+    // Two identical trees have no diff at all.
+    let left = new_smt(shared.clone());
+    let right = new_smt(shared.clone());
+    assert_eq!(diff(&left, &right).expect("diff"), Vec::<Diff<H256>>::new());
+
+    // Diverge two of the shared keys and add one key unique to `right`.
+    let mut right_pairs = shared.clone();
+    right_pairs[2].1 = H256::from([0xaa; 32]);
+    right_pairs[7].1 = H256::from([0xbb; 32]);
+    let extra_key = H256::from([0xee; 32]);
+    right_pairs.push((extra_key, H256::from([0xff; 32])));
+    let right = new_smt(right_pairs);
+
+    let mut diffs = diff(&left, &right).expect("diff");
+    diffs.sort_by_key(|d| d.key);
+    let mut expected = vec![
+        Diff {
+            key: shared[2].0,
+            left: shared[2].1,
+            right: H256::from([0xaa; 32]),
+        },
+        Diff {
+            key: shared[7].0,
+            left: shared[7].1,
+            right: H256::from([0xbb; 32]),
+        },
+        Diff {
+            key: extra_key,
+            left: H256::zero(),
+            right: H256::from([0xff; 32]),
+        },
+    ];
+    expected.sort_by_key(|d| d.key);
+    assert_eq!(diffs, expected);
+}
+
+#[test]
+fn test_cache_store_matches_uncached_root_under_eviction() {
+    use crate::cache_store::CacheStore;
+    use core::num::NonZeroUsize;
+
+    let pairs: Vec<(H256, H256)> = (0u8..50)
+        .map(|i| (H256::from([i; 32]), H256::from([i.wrapping_add(1); 32])))
+        .collect();
+
+    // Capacity far smaller than the number of branches a 50-leaf tree writes,
+    // so reads during construction are guaranteed to evict and refetch.
+    let cache_store = CacheStore::new(DefaultStore::<H256>::default(), NonZeroUsize::new(4).unwrap());
+    let mut cached_tree =
+        SparseMerkleTree::<Blake2bHasher, H256, _>::new_with_store(cache_store).expect("new_with_store");
+    for (key, value) in &pairs {
+        cached_tree.update(*key, *value).expect("update");
+    }
+
+    let uncached_tree = new_smt(pairs.clone());
+    assert_eq!(cached_tree.root(), uncached_tree.root());
+    for (key, value) in &pairs {
+        assert_eq!(cached_tree.get(key).expect("get"), *value);
+    }
+}
+
+#[test]
+fn test_cache_store_remove_branch_is_not_served_stale_from_cache() {
+    use crate::cache_store::CacheStore;
+    use core::num::NonZeroUsize;
+
+    let cache_store = CacheStore::new(DefaultStore::<H256>::default(), NonZeroUsize::new(16).unwrap());
+    let mut tree = SparseMerkleTree::<Blake2bHasher, H256, _>::new_with_store(cache_store).expect("new_with_store");
+
+    let key = H256::from([7u8; 32]);
+    tree.update(key, [1u8; 32].into()).expect("update");
+    assert_ne!(tree.get(&key).expect("get"), H256::zero());
+
+    // Deleting the only leaf removes every branch on its path; none of them
+    // should still answer from the cache afterwards.
+    tree.update(key, H256::zero()).expect("delete");
+    assert!(tree.root().is_zero());
+    assert_eq!(tree.get(&key).expect("get"), H256::zero());
+}
+
+#[test]
+fn test_checkpoint_smt_nested_rewind_and_drop() {
+    use crate::checkpoint::CheckpointSmt;
+
+    let key_a = H256::from([1u8; 32]);
+    let key_b = H256::from([2u8; 32]);
+    let key_c = H256::from([3u8; 32]);
+
+    // Independently-built trees give the expected root at each stage, since
+    // the root is just a deterministic function of which updates have been
+    // applied so far.
+    let root_after_a = *new_smt(vec![(key_a, [10u8; 32].into())]).root();
+    let root_after_a_b = *new_smt(vec![(key_a, [10u8; 32].into()), (key_b, [20u8; 32].into())]).root();
+
+    let mut smt = CheckpointSmt::<Blake2bHasher, H256, _>::new(H256::zero(), DefaultStore::<H256>::default());
+    smt.update(key_a, [10u8; 32].into()).expect("update a");
+    let outer = smt.checkpoint();
+    assert_eq!(*smt.root(), root_after_a);
+
+    smt.update(key_b, [20u8; 32].into()).expect("update b");
+    let inner = smt.checkpoint();
+    smt.update(key_c, [30u8; 32].into()).expect("update c");
+    assert_ne!(*smt.root(), root_after_a_b);
+
+    // Rewinding the inner checkpoint undoes only `key_c`'s update, leaving
+    // `key_b`'s still in place.
+    assert_eq!(*smt.rewind(inner).expect("rewind inner"), root_after_a_b);
+
+    // Re-open, make another change, but this time commit the inner frame so
+    // it merges into the outer one instead of being rewound directly.
+    let inner = smt.checkpoint();
+    smt.update(key_c, [31u8; 32].into()).expect("update c again");
+    smt.drop_checkpoint();
+    assert_ne!(*smt.root(), root_after_a_b);
+
+    // Rewinding the outer checkpoint undoes both the b and the merged c
+    // update, since drop_checkpoint folded c's undo log into the outer frame.
+    assert_eq!(*smt.rewind(outer).expect("rewind outer"), root_after_a);
+
+    // An id from before the tree was rewound past it is no longer valid.
+    assert!(matches!(smt.rewind(inner), Err(Error::Store(_))));
+}
+
+#[test]
+fn test_chunked_blob_piece_proof_round_trip_and_update() {
+    use crate::chunked_blob::{fold_piece, ChunkedBlob};
+
+    // 5 pieces of 4 bytes each: an odd piece count so the balanced tree has to
+    // promote an unpaired node at least once.
+    let data: Vec<u8> = (0u8..20).collect();
+    let blob = ChunkedBlob::<Blake2bHasher>::new(data, 4);
+    assert_eq!(blob.piece_count(), 5);
+
+    for i in 0..blob.piece_count() {
+        let proof = blob.piece_proof(i).expect("proof");
+        let piece = blob.piece(i).expect("piece").to_vec();
+        let leaf = {
+            let mut hasher = Blake2bHasher::default();
+            for &b in &piece {
+                hasher.write_byte(b);
+            }
+            hasher.finish()
+        };
+        assert_eq!(fold_piece::<Blake2bHasher>(leaf, &proof), blob.root());
+    }
+    assert!(blob.piece_proof(blob.piece_count()).is_none());
+
+    // Rewriting one piece must match a blob rebuilt from scratch with that
+    // piece replaced.
+    let new_piece = [0xAAu8; 4];
+    let updated_root = blob.root_after_update(2, &new_piece).expect("root_after_update");
+    let mut rebuilt_data = blob.data().to_vec();
+    rebuilt_data[8..12].copy_from_slice(&new_piece);
+    let rebuilt = ChunkedBlob::<Blake2bHasher>::new(rebuilt_data, 4);
+    assert_eq!(updated_root, rebuilt.root());
+    assert_ne!(updated_root, blob.root());
+}
+
+#[test]
+fn test_chunked_blob_as_smt_leaf_value() {
+    use crate::chunked_blob::ChunkedBlob;
+
+    type BlobSmt = SparseMerkleTree<Blake2bHasher, ChunkedBlob<Blake2bHasher>, DefaultStore<ChunkedBlob<Blake2bHasher>>>;
+
+    let mut tree = BlobSmt::default();
+    let key = H256::from([9u8; 32]);
+    let blob = ChunkedBlob::<Blake2bHasher>::new(vec![7u8; 10], 4);
+    let blob_root = blob.to_h256();
+
+    // `ChunkedBlob` slots into the main tree as a plain `Value`: only its
+    // piece-tree root is stored in the branch above it, regardless of how
+    // large the blob is.
+    tree.update(key, blob).expect("update");
+    let root_via_merkle_path = tree
+        .merkle_path(key)
+        .expect("merkle_path")
+        .compute_root::<Blake2bHasher>(key, blob_root);
+    assert_eq!(root_via_merkle_path, *tree.root());
+}
+
+#[test]
+fn test_mvcc_smt_read_txn_tracks_published_root() {
+    use crate::mvcc::MvccSmt;
+
+    let mut smt = MvccSmt::<Blake2bHasher, H256>::default();
+    let key = H256::from([1u8; 32]);
+
+    let mut txn = smt.write_txn();
+    txn.update(key, [1u8; 32].into()).expect("update");
+    txn.commit();
+
+    // A reader opened after the first `commit` sees the committed value at
+    // the published root.
+    let root_before = {
+        let reader_before = smt.read_txn();
+        assert_eq!(reader_before.get(&key).expect("get"), [1u8; 32].into());
+        assert_eq!(*reader_before.root(), *smt.root());
+        *reader_before.root()
+    };
+
+    let mut txn2 = smt.write_txn();
+    txn2.update(key, [2u8; 32].into()).expect("update");
+    txn2.commit();
+
+    // A reader created only after the second commit sees the new value; the
+    // published root moved forward exactly once.
+    let reader_after = smt.read_txn();
+    assert_eq!(reader_after.get(&key).expect("get"), [2u8; 32].into());
+    assert_eq!(*smt.root(), *reader_after.root());
+    assert_ne!(*reader_after.root(), root_before);
+}
+
+#[test]
+fn test_mvcc_smt_write_txn_discard_and_drop_roll_back() {
+    use crate::mvcc::MvccSmt;
+
+    let mut smt = MvccSmt::<Blake2bHasher, H256>::default();
+    let key = H256::from([2u8; 32]);
+
+    let mut txn = smt.write_txn();
+    txn.update(key, [9u8; 32].into()).expect("update");
+    txn.discard();
+    assert!(smt.root().is_zero());
+    assert_eq!(smt.read_txn().get(&key).expect("get"), H256::zero());
+
+    // An unfinished transaction rolls back the same way on drop.
+    {
+        let mut txn = smt.write_txn();
+        txn.update(key, [9u8; 32].into()).expect("update");
+    }
+    assert!(smt.root().is_zero());
+    assert_eq!(smt.read_txn().get(&key).expect("get"), H256::zero());
+
+    // A later commit still succeeds after the rolled-back attempts.
+    let mut txn = smt.write_txn();
+    txn.update(key, [3u8; 32].into()).expect("update");
+    txn.commit();
+    assert_eq!(smt.read_txn().get(&key).expect("get"), [3u8; 32].into());
+}
+
+#[test]
+fn test_auditable_smt_records_history_and_proves_versions() {
+    use crate::history::AuditableSmt;
+
+    let mut smt = AuditableSmt::<Blake2bHasher, H256>::default();
+    let key_a = H256::from([1u8; 32]);
+    let key_b = H256::from([2u8; 32]);
+
+    let root1 = smt.commit(vec![(key_a, [1u8; 32].into())]).expect("commit 1");
+    let commitment1 = smt.history_commitment();
+    let root2 = smt.commit(vec![(key_b, [2u8; 32].into())]).expect("commit 2");
+    let commitment2 = smt.history_commitment();
+    let root3 = smt.commit(vec![(key_a, [3u8; 32].into())]).expect("commit 3");
+    let commitment3 = smt.history_commitment();
+
+    // Each commitment differs as the MMR grows, and every version's inclusion
+    // proof, taken after the third commit, verifies against the latest commitment.
+    assert_ne!(commitment1, commitment2);
+    assert_ne!(commitment2, commitment3);
+    for (version, root) in [(1u64, root1), (2u64, root2), (3u64, root3)] {
+        let proof = smt.prove_version(version).expect("prove_version");
+        assert!(proof.verify::<Blake2bHasher>(&root, &commitment3));
+    }
+    // Tampering the claimed root fails verification against the same commitment.
+    let proof1 = smt.prove_version(1).expect("prove_version");
+    assert!(!proof1.verify::<Blake2bHasher>(&root2, &commitment3));
+    assert!(smt.prove_version(4).is_none());
+
+    // Historical membership proofs resolve against the root as of that commit,
+    // not the latest one.
+    let proof_at_1 = smt
+        .view_at(&root1, vec![key_a])
+        .expect("known root")
+        .expect("merkle_proof_at");
+    assert!(proof_at_1
+        .verify::<Blake2bHasher>(&root1, vec![(key_a, [1u8; 32].into())])
+        .expect("verify"));
+    assert!(smt.view_at(&H256::from([0xffu8; 32]), vec![key_a]).is_none());
+}
+
+#[test]
+fn test_versioned_smt_root_at_and_prune() {
+    use crate::versioned::VersionedSparseMerkleTree;
+
+    let mut tree = VersionedSparseMerkleTree::<Blake2bHasher, H256>::default();
+    let key = H256::from([5u8; 32]);
+
+    assert_eq!(tree.version(), 0);
+    assert!(tree.root_at(0).is_zero());
+
+    let root1 = tree.update(key, [1u8; 32].into()).expect("update v1");
+    let root2 = tree.update(key, [2u8; 32].into()).expect("update v2");
+    let root3 = tree.update(key, [3u8; 32].into()).expect("update v3");
+    assert_eq!(tree.version(), 3);
+    assert_ne!(root1, root2);
+    assert_ne!(root2, root3);
+
+    // Every past version's root and proof are still reachable.
+    assert_eq!(tree.root_at(1), root1);
+    assert_eq!(tree.root_at(2), root2);
+    assert_eq!(tree.root_at(3), root3);
+    // A version beyond the latest resolves to the newest root not exceeding it.
+    assert_eq!(tree.root_at(10), root3);
+
+    let proof_v1 = tree.merkle_proof_at(1, vec![key]).expect("proof v1");
+    assert!(proof_v1
+        .verify::<Blake2bHasher>(&root1, vec![(key, [1u8; 32].into())])
+        .expect("verify v1"));
+    let proof_v2 = tree.merkle_proof_at(2, vec![key]).expect("proof v2");
+    assert!(proof_v2
+        .verify::<Blake2bHasher>(&root2, vec![(key, [2u8; 32].into())])
+        .expect("verify v2"));
+
+    // Pruning before version 3 keeps the newest entry strictly older than 3
+    // (version 2) as the new baseline; anything before that is unreachable.
+    tree.prune(3);
+    assert_eq!(tree.root_at(3), root3);
+    assert_eq!(tree.root_at(2), root2);
+    assert!(tree.root_at(1).is_zero());
+}
+
+#[test]
+fn test_versioned_smt_namespaces_are_isolated_over_one_store() {
+    use crate::multi_tree::VersionedSMT;
+
+    let mut vsmt = VersionedSMT::<Blake2bHasher, H256>::new();
+    let tree_a: Vec<u8> = b"tree-a".to_vec();
+    let tree_b: Vec<u8> = b"tree-b".to_vec();
+    let key = H256::from([1u8; 32]);
+
+    assert!(vsmt.root(&tree_a).is_zero());
+    assert!(vsmt.root(&tree_b).is_zero());
+
+    vsmt.update(&tree_a, key, [1u8; 32].into()).expect("update a");
+    vsmt.update(&tree_b, key, [2u8; 32].into()).expect("update b");
+
+    // The same key under the same store resolves independently per tree id.
+    assert_eq!(vsmt.get(&tree_a, &key).expect("get a"), [1u8; 32].into());
+    assert_eq!(vsmt.get(&tree_b, &key).expect("get b"), [2u8; 32].into());
+    assert_ne!(vsmt.root(&tree_a), vsmt.root(&tree_b));
+
+    let mut ids = vsmt.store().tree_ids();
+    ids.sort();
+    let mut expected = vec![tree_a.clone(), tree_b.clone()];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    // Reconstructing a fresh VersionedSMT from the raw store preserves both
+    // namespaces and their roots.
+    let store = vsmt.store().clone();
+    let mut rebuilt = VersionedSMT::<Blake2bHasher, H256>::from_store(store).expect("from_store");
+    assert_eq!(rebuilt.root(&tree_a), vsmt.root(&tree_a));
+    assert_eq!(rebuilt.root(&tree_b), vsmt.root(&tree_b));
+    assert_eq!(rebuilt.get(&tree_a, &key).expect("get a"), [1u8; 32].into());
+    assert_eq!(rebuilt.get(&tree_b, &key).expect("get b"), [2u8; 32].into());
+}
+
+#[test]
+fn test_vs_smt_isolates_fixed_width_xids() {
+    use crate::multi_tree::VsSmt;
+
+    let mut vs = VsSmt::<Blake2bHasher, H256>::new();
+    let xid1 = [1u8; 16];
+    let xid2 = [2u8; 16];
+    let key = H256::from([9u8; 32]);
+
+    vs.update(&xid1, key, [10u8; 32].into()).expect("update xid1");
+    vs.update(&xid2, key, [20u8; 32].into()).expect("update xid2");
+
+    assert_eq!(vs.get(&xid1, &key).expect("get xid1"), [10u8; 32].into());
+    assert_eq!(vs.get(&xid2, &key).expect("get xid2"), [20u8; 32].into());
+    assert_ne!(vs.root(&xid1), vs.root(&xid2));
+
+    let proof = vs.merkle_proof(&xid1, vec![key]).expect("merkle_proof");
+    assert!(proof
+        .verify::<Blake2bHasher>(&vs.root(&xid1), vec![(key, [10u8; 32].into())])
+        .expect("verify"));
+
+    // Rebuilding from the shared store preserves both xid namespaces.
+    let store = vs.store().clone();
+    let mut rebuilt = VsSmt::<Blake2bHasher, H256>::from_store(store).expect("from_store");
+    assert_eq!(rebuilt.get(&xid1, &key).expect("get xid1"), [10u8; 32].into());
+    assert_eq!(rebuilt.get(&xid2, &key).expect("get xid2"), [20u8; 32].into());
+}
+
+#[cfg(feature = "blake3")]
+#[test]
+fn test_blake3_smt_alias_generic_apis() {
+    use crate::blake3_hasher::Blake3Smt;
+
+    // The `Blake3Smt` alias lets callers drive the hash-agnostic update/root/
+    // merkle_proof/verify APIs at the type level with no per-hasher glue.
+    let mut tree = Blake3Smt::<H256>::default();
+    let pairs: Vec<(H256, H256)> = (0u8..8)
+        .map(|i| (H256::from([i; 32]), H256::from([i.wrapping_add(1); 32])))
+        .collect();
+    for (key, value) in &pairs {
+        tree.update(*key, *value).expect("update");
+        assert_eq!(&tree.get(key).expect("get"), value);
+    }
+
+    let keys: Vec<H256> = pairs.iter().map(|(k, _)| *k).collect();
+    let proof = tree.merkle_proof(keys).expect("proof");
+    assert!(proof
+        .verify::<crate::blake3_hasher::Blake3Hasher>(tree.root(), pairs)
+        .expect("verify"));
+}
@@ -1,9 +1,11 @@
 #![allow(dead_code)]
 
 use crate::{
-    collections::VecDeque,
+    borrow::Cow,
+    circuit::{CircuitWitness, TREE_HEIGHT},
     error::{Error, Result},
     merge::{merge, MergeValue},
+    merkle_path::MerklePath,
     merkle_proof::MerkleProof,
     traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
     vec::Vec,
@@ -60,24 +62,76 @@ impl BranchNode {
     }
 }
 
+/// The default tree height: every one of the 256 key bits is walked.
+pub const DEFAULT_MAX_HEIGHT: u8 = u8::MAX;
+
 /// Sparse merkle tree
-#[derive(Default)]
 pub struct SparseMerkleTree<H, V, S> {
     store: S,
     root: H256,
+    // Highest branch height walked by `update`/`get`/`merkle_proof`; a full
+    // 256-bit tree uses `DEFAULT_MAX_HEIGHT`, shorter keys a smaller value.
+    max_height: u8,
     phantom: PhantomData<(H, V)>,
 }
 
+impl<H, V, S: Default> Default for SparseMerkleTree<H, V, S> {
+    fn default() -> Self {
+        SparseMerkleTree {
+            store: S::default(),
+            root: H256::zero(),
+            max_height: DEFAULT_MAX_HEIGHT,
+            phantom: PhantomData,
+        }
+    }
+}
+
 impl<H, V, S> SparseMerkleTree<H, V, S> {
-    /// Build a merkle tree from root and store
+    /// Build a full-height (256-bit) merkle tree from root and store
     pub fn new(root: H256, store: S) -> SparseMerkleTree<H, V, S> {
         SparseMerkleTree {
             root,
             store,
+            max_height: DEFAULT_MAX_HEIGHT,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Build a merkle tree that walks only the bottom `height_bits` bits of
+    /// each key (height 0 is the leaf level, height 255 the root), so trees
+    /// over keys shorter than 32 bytes write fewer branches and produce
+    /// shorter proofs. `height_bits` is clamped to at least one level. Every
+    /// key passed to [`update`](Self::update), [`update_all`](Self::update_all)
+    /// or [`merkle_path`](Self::merkle_path) must have all of its bits above
+    /// `height_bits - 1` cleared, or it is rejected with [`Error::Store`].
+    pub fn new_with_height(root: H256, store: S, height_bits: u16) -> SparseMerkleTree<H, V, S> {
+        let max_height = (height_bits.clamp(1, 256) - 1) as u8;
+        SparseMerkleTree {
+            root,
+            store,
+            max_height,
             phantom: PhantomData,
         }
     }
 
+    /// The highest branch height this tree walks (`key bits - 1`).
+    pub fn max_height(&self) -> u8 {
+        self.max_height
+    }
+
+    /// Reject a key that has any bit set above `max_height`: such a key would
+    /// silently collide in the branch positions this tree actually walks,
+    /// since only its bottom `max_height + 1` bits are ever examined.
+    fn check_key_in_range(&self, key: &H256) -> Result<()> {
+        if self.max_height == DEFAULT_MAX_HEIGHT || key.parent_path(self.max_height).is_zero() {
+            Ok(())
+        } else {
+            Err(Error::Store(
+                "key has bits set above max_height, which this tree (built via new_with_height) does not walk".into(),
+            ))
+        }
+    }
+
     /// Merkle root
     pub fn root(&self) -> &H256 {
         &self.root
@@ -125,6 +179,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>>
     /// Update a leaf, return new merkle root
     /// set to zero value to delete a key
     pub fn update(&mut self, key: H256, value: V) -> Result<&H256> {
+        self.check_key_in_range(&key)?;
         // compute and store new leaf
         let node = MergeValue::from_h256(value.to_h256());
         // notice when value is zero the leaf is deleted, so we do not need to store it
@@ -137,11 +192,11 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>>
         // recompute the tree from bottom to top
         let mut current_key = key;
         let mut current_node = node;
-        for height in 0..=u8::MAX {
+        for height in 0..=self.max_height {
             let parent_key = current_key.parent_path(height);
             let parent_branch_key = BranchKey::new(height, parent_key);
             let (left, right) =
-                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
+                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
                     if current_key.is_right(height) {
                         (parent_branch.left, current_node)
                     } else {
@@ -175,14 +230,29 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>>
         Ok(&self.root)
     }
 
-    /// Update multiple leaves at once
+    /// Update multiple leaves at once.
+    ///
+    /// Rather than replaying the per-key bottom-up walk for every leaf (which
+    /// re-reads and re-hashes the branch paths shared by neighbouring keys),
+    /// this runs a single left-to-right pass over the sorted keys, reusing the
+    /// `fork_height` grouping proven out in `merkle_proof`. A stack holds the
+    /// partially merged subtrees keyed by fork height: a leaf climbs only up to
+    /// its fork with the next key and parks its subtree there; the next leaf
+    /// pops it as the left sibling when it reaches that height. Every distinct
+    /// `BranchKey` is therefore written once and each node's `merge::<H>` runs
+    /// exactly once, turning N·256 branch touches into roughly the number of
+    /// distinct branch positions on the union of the key paths.
     pub fn update_all(&mut self, mut leaves: Vec<(H256, V)>) -> Result<&H256> {
+        for (key, _) in &leaves {
+            self.check_key_in_range(key)?;
+        }
         // Dedup(only keep the last of each key) and sort leaves
         leaves.reverse();
         leaves.sort_by_key(|(a, _)| *a);
         leaves.dedup_by_key(|(a, _)| *a);
 
-        let mut nodes = leaves
+        // Persist the leaves and keep their merge values in sorted order.
+        let nodes = leaves
             .into_iter()
             .map(|(k, v)| {
                 let value = MergeValue::from_h256(v.to_h256());
@@ -191,66 +261,79 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>>
                 } else {
                     self.store.remove_leaf(&k)
                 }
-                .map(|_| (k, value, 0))
+                .map(|_| (k, value))
             })
-            .collect::<Result<VecDeque<(H256, MergeValue, u8)>>>()?;
+            .collect::<Result<Vec<(H256, MergeValue)>>>()?;
 
-        while let Some((current_key, current_merge_value, height)) = nodes.pop_front() {
-            let parent_key = current_key.parent_path(height);
-            let parent_branch_key = BranchKey::new(height, parent_key);
+        if nodes.is_empty() {
+            return Ok(&self.root);
+        }
 
-            // Test for neighbors
-            let mut right = None;
-            if !current_key.is_right(height) && !nodes.is_empty() {
-                let (neighbor_key, _, neighbor_height) = nodes.front().expect("nodes is not empty");
-                if neighbor_height.eq(&height) {
-                    let mut right_key = current_key;
-                    right_key.set_bit(height);
-                    if neighbor_key.eq(&right_key) {
-                        let (_, neighbor_value, _) = nodes.pop_front().expect("nodes is not empty");
-                        right = Some(neighbor_value);
-                    }
-                }
-            }
+        // Stack of parked subtrees, parallel arrays of value and fork height.
+        let mut stack_values: Vec<MergeValue> = Vec::new();
+        let mut stack_fork_height: Vec<u8> = Vec::new();
 
-            let (left, right) = if let Some(right_merge_value) = right {
-                (current_merge_value, right_merge_value)
+        let mut leaf_index = 0;
+        while leaf_index < nodes.len() {
+            let leaf_key = nodes[leaf_index].0;
+            let is_final = leaf_index + 1 == nodes.len();
+            let fork_height = if is_final {
+                self.max_height
             } else {
-                // In case neighbor is not available, fetch from store
-                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
-                    if current_key.is_right(height) {
-                        (parent_branch.left, current_merge_value)
+                leaf_key.fork_height(&nodes[leaf_index + 1].0)
+            };
+
+            let mut current_node = nodes[leaf_index].1.clone();
+            for height in 0..=fork_height {
+                if height == fork_height && !is_final {
+                    // Park this subtree for the next key to pick up as its left sibling.
+                    break;
+                }
+                let parent_key = leaf_key.parent_path(height);
+                let parent_branch_key = BranchKey::new(height, parent_key);
+
+                let (left, right) = if stack_fork_height.last() == Some(&height) {
+                    // Merge with the previously parked (left) subtree.
+                    stack_fork_height.pop();
+                    let left_sub = stack_values.pop().expect("parked subtree");
+                    (left_sub, current_node)
+                } else if let Some(parent_branch) =
+                    self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned)
+                {
+                    // Preserve the untouched sibling already in the store.
+                    if leaf_key.is_right(height) {
+                        (parent_branch.left, current_node)
                     } else {
-                        (current_merge_value, parent_branch.right)
+                        (current_node, parent_branch.right)
                     }
-                } else if current_key.is_right(height) {
-                    (MergeValue::zero(), current_merge_value)
+                } else if leaf_key.is_right(height) {
+                    (MergeValue::zero(), current_node)
+                } else {
+                    (current_node, MergeValue::zero())
+                };
+
+                if !left.is_zero() || !right.is_zero() {
+                    self.store.insert_branch(
+                        parent_branch_key,
+                        BranchNode {
+                            left: left.clone(),
+                            right: right.clone(),
+                        },
+                    )?;
                 } else {
-                    (current_merge_value, MergeValue::zero())
+                    self.store.remove_branch(&parent_branch_key)?;
                 }
-            };
 
-            if !left.is_zero() || !right.is_zero() {
-                self.store.insert_branch(
-                    parent_branch_key,
-                    BranchNode {
-                        left: left.clone(),
-                        right: right.clone(),
-                    },
-                )?;
-            } else {
-                self.store.remove_branch(&parent_branch_key)?;
+                current_node = merge::<H>(height, &parent_key, &left, &right);
             }
-            if height == u8::MAX {
-                self.root = merge::<H>(height, &parent_key, &left, &right).hash::<H>();
-                break;
+
+            if is_final {
+                self.root = current_node.hash::<H>();
             } else {
-                nodes.push_back((
-                    parent_key,
-                    merge::<H>(height, &parent_key, &left, &right),
-                    height + 1,
-                ));
+                stack_values.push(current_node);
+                stack_fork_height.push(fork_height);
             }
+            leaf_index += 1;
         }
 
         Ok(&self.root)
@@ -264,7 +347,145 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
         if self.is_empty() {
             return Ok(V::zero());
         }
-        Ok(self.store.get_leaf(key)?.unwrap_or_else(V::zero))
+        Ok(self.store.get_leaf(key)?.map(Cow::into_owned).unwrap_or_else(V::zero))
+    }
+
+    /// Generate a compact single-key opening, ordered leaf-to-root.
+    ///
+    /// The returned [`MerklePath`] proves membership when the key is present and
+    /// non-membership (a zero folded value) otherwise.
+    pub fn merkle_path(&self, key: H256) -> Result<MerklePath> {
+        self.check_key_in_range(&key)?;
+        let mut siblings = Vec::with_capacity(self.max_height as usize + 1);
+        for height in 0..=self.max_height {
+            let parent_key = key.parent_path(height);
+            let parent_branch_key = BranchKey::new(height, parent_key);
+            let sibling = match self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
+                Some(parent_branch) => {
+                    let sibling = if key.is_right(height) {
+                        parent_branch.left
+                    } else {
+                        parent_branch.right
+                    };
+                    if sibling.is_zero() {
+                        None
+                    } else {
+                        Some(sibling)
+                    }
+                }
+                None => None,
+            };
+            siblings.push(sibling);
+        }
+        Ok(MerklePath::new(siblings))
+    }
+
+    /// Generate a canonical, fixed-depth inclusion witness for `key`.
+    ///
+    /// Unlike [`merkle_path`](Self::merkle_path), the returned [`CircuitWitness`]
+    /// always carries exactly [`TREE_HEIGHT`] siblings ordered leaf-to-root,
+    /// substituting the zero sentinel `H256::zero()` wherever the store holds no
+    /// node, so the path has no variable-length program and folds to
+    /// [`root`](Self::root) inside an arithmetic circuit. The witness also
+    /// records the leaf hash and the key's bit decomposition.
+    pub fn circuit_witness(&self, key: H256) -> Result<CircuitWitness> {
+        let mut auth_path = Vec::with_capacity(TREE_HEIGHT);
+        let mut key_bits = Vec::with_capacity(TREE_HEIGHT);
+        for height in 0..TREE_HEIGHT {
+            let height = height as u8;
+            let parent_key = key.parent_path(height);
+            let parent_branch_key = BranchKey::new(height, parent_key);
+            let sibling = match self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
+                Some(parent_branch) => {
+                    let sibling = if key.is_right(height) {
+                        parent_branch.left
+                    } else {
+                        parent_branch.right
+                    };
+                    if sibling.is_zero() {
+                        H256::zero()
+                    } else {
+                        sibling.hash::<H>()
+                    }
+                }
+                None => H256::zero(),
+            };
+            auth_path.push(sibling);
+            key_bits.push(key.is_right(height));
+        }
+        let leaf_hash = self.get(&key)?.to_h256();
+        Ok(CircuitWitness::new(key, leaf_hash, auth_path, key_bits))
+    }
+
+    /// Validate the persisted store rather than trusting it.
+    ///
+    /// Starting from the root branch, every stored [`BranchNode`] is re-hashed
+    /// via [`merge`] and checked against the child [`MergeValue`] its parent
+    /// recorded; a node whose two children are both zero (which `update` would
+    /// have pruned) is rejected, as is any node whose recomputed hash does not
+    /// match the root it should produce. On the first violation an
+    /// [`Error::MissingBranch`] naming the offending height and node key is
+    /// returned — both for a branch absent from the store and for one present
+    /// but hash-inconsistent with its parent or the root. This is worth
+    /// running after crash recovery or when loading an untrusted backend.
+    pub fn verify_integrity(&self) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let root_key = BranchKey::new(self.max_height, H256::zero());
+        // Worklist of (branch to check, value its parent expects for it); the
+        // root has no parent and is checked against `self.root` instead.
+        let mut stack: Vec<(BranchKey, Option<MergeValue>)> = Vec::new();
+        stack.push((root_key, None));
+
+        while let Some((branch_key, expected)) = stack.pop() {
+            let node = self
+                .store
+                .get_branch(&branch_key)?
+                .map(Cow::into_owned)
+                .ok_or(Error::MissingBranch(branch_key.height, branch_key.node_key))?;
+
+            // A branch with both children zero should have been pruned by `update`.
+            if node.is_empty() {
+                return Err(Error::MissingBranch(branch_key.height, branch_key.node_key));
+            }
+
+            let recomputed = merge::<H>(branch_key.height, &branch_key.node_key, &node.left, &node.right);
+            match expected {
+                Some(parent_value) => {
+                    if parent_value != recomputed {
+                        return Err(Error::MissingBranch(branch_key.height, branch_key.node_key));
+                    }
+                }
+                None => {
+                    if recomputed.hash::<H>() != self.root {
+                        return Err(Error::MissingBranch(branch_key.height, branch_key.node_key));
+                    }
+                }
+            }
+
+            // Descend into any child that is itself a stored branch; terminal
+            // leaf / shortcut values have no branch to recurse into.
+            if branch_key.height > 0 {
+                let child_height = branch_key.height - 1;
+                let left_key = branch_key.node_key;
+                let mut right_key = branch_key.node_key;
+                right_key.set_bit(branch_key.height);
+                for (child_node_key, child_value) in
+                    [(left_key, &node.left), (right_key, &node.right)]
+                {
+                    if child_value.is_zero() {
+                        continue;
+                    }
+                    let child_branch_key = BranchKey::new(child_height, child_node_key);
+                    if self.store.get_branch(&child_branch_key)?.is_some() {
+                        stack.push((child_branch_key, Some(child_value.clone())));
+                    }
+                }
+            }
+        }
+
+        Ok(())
     }
 
     /// Generate merkle proof
@@ -280,10 +501,10 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
         let mut leaves_bitmap: Vec<H256> = Default::default();
         for current_key in &keys {
             let mut bitmap = H256::zero();
-            for height in 0..=u8::MAX {
+            for height in 0..=self.max_height {
                 let parent_key = current_key.parent_path(height);
                 let parent_branch_key = BranchKey::new(height, parent_key);
-                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
+                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
                     let sibling = if current_key.is_right(height) {
                         parent_branch.left
                     } else {
@@ -308,7 +529,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
             let fork_height = if leaf_index + 1 < keys.len() {
                 leaf_key.fork_height(&keys[leaf_index + 1])
             } else {
-                u8::MAX
+                self.max_height
             };
             for height in 0..=fork_height {
                 if height == fork_height && leaf_index + 1 < keys.len() {
@@ -323,7 +544,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
                     stack_top -= 1;
                 } else if leaves_bitmap[leaf_index].get_bit(height) {
                     let parent_branch_key = BranchKey::new(height, parent_key);
-                    if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
+                    if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
                         let sibling = if is_right {
                             parent_branch.left
                         } else {
@@ -1,4 +1,5 @@
 use crate::{
+    borrow::Cow,
     error::{Error, Result},
     merge::{into_merge_value, merge, MergeValue},
     merkle_proof::MerkleProof,
@@ -91,7 +92,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>>
             // walk from top to bottom
             let node_key = key.parent_path(last_height);
             let branch_key = BranchKey::new(last_height, node_key); // this represents a position in the tree
-            if let Some(branch) = self.store.get_branch(&branch_key)? {
+            if let Some(branch) = self.store.get_branch(&branch_key)?.map(Cow::into_owned) {
                 // if we we found a record in here
                 // we need to determine whether is it a shortcut
                 let (target, another) = if key.is_right(last_height) {
@@ -231,7 +232,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>>
             let node_key = key.parent_path(height);
             let branch_key = BranchKey::new(height, node_key);
 
-            let new_merge = if let Some(branch) = self.store.get_branch(&branch_key)? {
+            let new_merge = if let Some(branch) = self.store.get_branch(&branch_key)?.map(Cow::into_owned) {
                 merge::<H>(height, &node_key, &branch.left, &branch.right)
             } else {
                 MergeValue::zero()
@@ -247,7 +248,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>>
                     // move up
                     self.store.remove_branch(&branch_key)?;
                 }
-                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
+                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
                     let (left, right) = if key.is_right(height + 1) {
                         (parent_branch.left, new_merge)
                     } else {
@@ -303,7 +304,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
         if self.is_empty() {
             return Ok(V::zero());
         }
-        Ok(self.store.get_leaf(key)?.unwrap_or_else(V::zero))
+        Ok(self.store.get_leaf(key)?.map(Cow::into_owned).unwrap_or_else(V::zero))
     }
 
     /// Generate merkle proof
@@ -322,7 +323,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
             for height in (0..=core::u8::MAX).rev() {
                 let parent_key = current_key.parent_path(height);
                 let parent_branch_key = BranchKey::new(height, parent_key);
-                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
+                if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
                     let (sibling, target) = if current_key.is_right(height) {
                         (parent_branch.left, parent_branch.right)
                     } else {
@@ -380,7 +381,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
                     let parent_key = leaf_key.parent_path(height);
                     let is_right = leaf_key.is_right(height);
                     let parent_branch_key = BranchKey::new(height, parent_key);
-                    if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
+                    if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned) {
                         let (sibling, current) = if is_right {
                             (parent_branch.left, parent_branch.right)
                         } else {
@@ -413,7 +414,7 @@ impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> SparseMerkleTree<H, V, S
                             let is_right = leaf_key.is_right(i);
                             let parent_branch_key = BranchKey::new(i, parent_key);
                             if let Some(parent_branch) =
-                                self.store.get_branch(&parent_branch_key)?
+                                self.store.get_branch(&parent_branch_key)?.map(Cow::into_owned)
                             {
                                 let current = if is_right {
                                     parent_branch.right
@@ -0,0 +1,173 @@
+//! A versioned sparse merkle tree that retains previous roots.
+//!
+//! `VersionedSparseMerkleTree` layers a copy-on-write store over the regular
+//! `merge()`/`MergeValue` machinery: every `update` bumps a monotonically
+//! increasing version and records the branch nodes it touches under
+//! `(version, BranchKey)`. Reads resolve to the newest entry not exceeding the
+//! requested version, so `root_at`/`merkle_proof_at` reconstruct the tree as of
+//! any past version and `prune` reclaims superseded nodes.
+
+use crate::{
+    borrow::Cow,
+    collections::BTreeMap,
+    error::Result,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
+    tree::{BranchKey, BranchNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+use core::marker::PhantomData;
+
+/// Version counter, bumped once per committed update.
+pub type Version = u64;
+
+/// Copy-on-write store keying every branch/leaf write by the version that wrote
+/// it. A point-in-time view is the set of newest writes with `version <= v`.
+#[derive(Debug, Clone, Default)]
+pub struct VersionedStore<V> {
+    branches: BTreeMap<BranchKey, BTreeMap<Version, Option<BranchNode>>>,
+    leaves: BTreeMap<H256, BTreeMap<Version, Option<V>>>,
+    at: Version,
+}
+
+impl<V: Clone> VersionedStore<V> {
+    /// Resolve the branch node visible at `version`.
+    fn branch_at(&self, version: Version, key: &BranchKey) -> Option<BranchNode> {
+        self.branches
+            .get(key)
+            .and_then(|history| history.range(..=version).next_back())
+            .and_then(|(_, node)| node.clone())
+    }
+
+    /// Resolve the leaf visible at `version`.
+    fn leaf_at(&self, version: Version, key: &H256) -> Option<V> {
+        self.leaves
+            .get(key)
+            .and_then(|history| history.range(..=version).next_back())
+            .and_then(|(_, leaf)| leaf.clone())
+    }
+
+    /// Drop every entry wholly superseded before `before_version`.
+    pub fn prune(&mut self, before_version: Version) {
+        for history in self.branches.values_mut() {
+            prune_history(history, before_version);
+        }
+        for history in self.leaves.values_mut() {
+            prune_history(history, before_version);
+        }
+        self.branches.retain(|_, history| !history.is_empty());
+        self.leaves.retain(|_, history| !history.is_empty());
+    }
+}
+
+/// Keep the newest entry strictly older than `before_version` as the baseline
+/// plus everything at or after it; everything older is unreachable.
+fn prune_history<T>(history: &mut BTreeMap<Version, T>, before_version: Version) {
+    let baseline = history
+        .range(..before_version)
+        .next_back()
+        .map(|(v, _)| *v);
+    if let Some(baseline) = baseline {
+        history.retain(|v, _| *v >= baseline);
+    }
+}
+
+impl<V: Clone> StoreReadOps<V> for VersionedStore<V> {
+    fn get_branch(&self, key: &BranchKey) -> Result<Option<Cow<'_, BranchNode>>> {
+        Ok(self.branch_at(self.at, key).map(Cow::Owned))
+    }
+    fn get_leaf(&self, key: &H256) -> Result<Option<Cow<'_, V>>> {
+        Ok(self.leaf_at(self.at, key).map(Cow::Owned))
+    }
+}
+
+impl<V: Clone> StoreWriteOps<V> for VersionedStore<V> {
+    fn insert_branch(&mut self, key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.branches.entry(key).or_default().insert(self.at, Some(branch));
+        Ok(())
+    }
+    fn insert_leaf(&mut self, key: H256, leaf: V) -> Result<()> {
+        self.leaves.entry(key).or_default().insert(self.at, Some(leaf));
+        Ok(())
+    }
+    fn remove_branch(&mut self, key: &BranchKey) -> Result<()> {
+        if self.branches.contains_key(key) {
+            self.branches.get_mut(key).unwrap().insert(self.at, None);
+        }
+        Ok(())
+    }
+    fn remove_leaf(&mut self, key: &H256) -> Result<()> {
+        if self.leaves.contains_key(key) {
+            self.leaves.get_mut(key).unwrap().insert(self.at, None);
+        }
+        Ok(())
+    }
+}
+
+/// A sparse merkle tree that remembers the root of every past version.
+#[derive(Default)]
+pub struct VersionedSparseMerkleTree<H, V> {
+    store: VersionedStore<V>,
+    roots: BTreeMap<Version, H256>,
+    version: Version,
+    phantom: PhantomData<H>,
+}
+
+impl<H: Hasher + Default, V: Value + Clone> VersionedSparseMerkleTree<H, V> {
+    /// The current (latest) version.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Apply a batch of updates as a new version and return its root.
+    pub fn update_all(&mut self, leaves: Vec<(H256, V)>) -> Result<H256> {
+        let next = self.version + 1;
+        self.store.at = next;
+        let mut tree = self.take_tree(self.version);
+        tree.update_all(leaves)?;
+        let root = *tree.root();
+        self.store = tree.take_store();
+        self.version = next;
+        self.roots.insert(next, root);
+        Ok(root)
+    }
+
+    /// Apply a single update as a new version and return its root.
+    pub fn update(&mut self, key: H256, value: V) -> Result<H256> {
+        self.update_all(crate::vec![(key, value)])
+    }
+
+    /// The root as of `version`, or the empty root for version 0.
+    pub fn root_at(&self, version: Version) -> H256 {
+        self.roots
+            .range(..=version)
+            .next_back()
+            .map(|(_, root)| *root)
+            .unwrap_or_else(H256::zero)
+    }
+
+    /// Generate a merkle proof against the tree state as of `version`.
+    pub fn merkle_proof_at(&self, version: Version, keys: Vec<H256>) -> Result<MerkleProof> {
+        self.read_tree(version).merkle_proof(keys)
+    }
+
+    /// Reclaim nodes that no version at or after `before_version` can reach.
+    pub fn prune(&mut self, before_version: Version) {
+        self.store.prune(before_version);
+        prune_history(&mut self.roots, before_version);
+    }
+
+    /// A read-only `SparseMerkleTree` view pinned to `version`.
+    fn read_tree(&self, version: Version) -> SparseMerkleTree<H, V, VersionedStore<V>> {
+        let mut store = self.store.clone();
+        store.at = version;
+        SparseMerkleTree::new(self.root_at(version), store)
+    }
+
+    /// Take ownership of a mutable tree pinned to `version` for recomputation.
+    fn take_tree(&mut self, version: Version) -> SparseMerkleTree<H, V, VersionedStore<V>> {
+        let store = core::mem::take(&mut self.store);
+        SparseMerkleTree::new(self.root_at(version), store)
+    }
+}
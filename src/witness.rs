@@ -0,0 +1,132 @@
+//! Maintained incremental witnesses for a watched set of keys.
+//!
+//! Re-deriving a key's authentication path with `merkle_proof` walks the whole
+//! store on every call. [`WitnessSmt`] instead keeps, for each watched key, the
+//! ordered list of sibling [`MergeValue`]s along its root-to-leaf path and
+//! refreshes only the entries an `update` can actually disturb: an update to
+//! `key` can change a watched key `w`'s path only at `fork_height(key, w)` — the
+//! single height where `key` descends into `w`'s sibling subtree — so the
+//! refresh is O(depth) to read and O(1) per watched key, and [`witness`] just
+//! assembles the cached siblings into a [`MerkleProof`].
+
+use crate::{
+    collections::Map,
+    error::Result,
+    merge::MergeValue,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, StoreReadOps, StoreWriteOps, Value},
+    tree::{BranchKey, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+
+/// A tree wrapper that maintains authentication paths for watched keys.
+#[derive(Default)]
+pub struct WitnessSmt<H, V, S> {
+    tree: SparseMerkleTree<H, V, S>,
+    // For each watched key: its 256 siblings ordered leaf -> root, `None` where
+    // the sibling subtree is empty.
+    watched: Map<H256, Vec<Option<MergeValue>>>,
+}
+
+impl<H, V, S> WitnessSmt<H, V, S> {
+    /// Wrap an existing tree; nothing is watched until [`watch`](Self::watch).
+    pub fn new(tree: SparseMerkleTree<H, V, S>) -> Self {
+        WitnessSmt {
+            tree,
+            watched: Default::default(),
+        }
+    }
+
+    /// Borrow the underlying tree.
+    pub fn tree(&self) -> &SparseMerkleTree<H, V, S> {
+        &self.tree
+    }
+
+    /// Stop maintaining a witness for `key`.
+    pub fn unwatch(&mut self, key: &H256) {
+        self.watched.remove(key);
+    }
+}
+
+impl<H: Hasher + Default, V: Value, S: StoreReadOps<V>> WitnessSmt<H, V, S> {
+    /// Start maintaining a witness for `key`, seeding its siblings from the store.
+    pub fn watch(&mut self, key: H256) -> Result<()> {
+        let path = self.collect_path(&key)?;
+        self.watched.insert(key, path);
+        Ok(())
+    }
+
+    /// Retrieve the maintained witness for `key` as a single-key [`MerkleProof`].
+    ///
+    /// A key that was never watched is read from the store on demand.
+    pub fn witness(&self, key: &H256) -> Result<MerkleProof> {
+        let path = match self.watched.get(key) {
+            Some(path) => path.clone(),
+            None => self.collect_path(key)?,
+        };
+        Ok(path_to_proof(&path))
+    }
+
+    /// Read the full sibling path for `key` directly from the store.
+    fn collect_path(&self, key: &H256) -> Result<Vec<Option<MergeValue>>> {
+        let mut siblings = Vec::with_capacity(256);
+        for height in 0..=u8::MAX {
+            siblings.push(self.sibling_at(key, height)?);
+        }
+        Ok(siblings)
+    }
+
+    /// The non-zero sibling of `key` at `height`, or `None` for an empty subtree.
+    fn sibling_at(&self, key: &H256, height: u8) -> Result<Option<MergeValue>> {
+        let parent_branch_key = BranchKey::new(height, key.parent_path(height));
+        Ok(self
+            .tree
+            .store()
+            .get_branch(&parent_branch_key)?
+            .map(|branch| {
+                let branch = branch.into_owned();
+                if key.is_right(height) {
+                    branch.left
+                } else {
+                    branch.right
+                }
+            })
+            .filter(|sibling| !sibling.is_zero()))
+    }
+}
+
+impl<H: Hasher + Default, V: Value, S: StoreReadOps<V> + StoreWriteOps<V>> WitnessSmt<H, V, S> {
+    /// Update a leaf, then refresh every maintained witness in O(1) each.
+    pub fn update(&mut self, key: H256, value: V) -> Result<&H256> {
+        self.tree.update(key, value)?;
+        // Refresh the one sibling of each watched key that this update can touch.
+        let watched_keys: Vec<H256> = self.watched.keys().copied().collect();
+        for watched_key in watched_keys {
+            if watched_key == key {
+                continue;
+            }
+            let height = key.fork_height(&watched_key);
+            let sibling = self.sibling_at(&watched_key, height)?;
+            if let Some(path) = self.watched.get_mut(&watched_key) {
+                path[height as usize] = sibling;
+            }
+        }
+        Ok(self.tree.root())
+    }
+}
+
+/// Assemble a leaf-to-root sibling list into a single-key [`MerkleProof`].
+fn path_to_proof(path: &[Option<MergeValue>]) -> MerkleProof {
+    let mut bitmap = H256::zero();
+    let mut merkle_path = Vec::new();
+    for (height, sibling) in path.iter().enumerate() {
+        if let Some(sibling) = sibling {
+            bitmap.set_bit(height as u8);
+            merkle_path.push(sibling.clone());
+        }
+    }
+    let mut leaves_bitmap = Vec::with_capacity(1);
+    leaves_bitmap.push(bitmap);
+    MerkleProof::new(leaves_bitmap, merkle_path)
+}
@@ -84,6 +84,55 @@ impl CkbSmt {
         }
     }
 
+    /// Return the stored value for `key`, or 32 zero bytes if absent.
+    pub fn get(&self, key: &Uint8Array) -> Uint8Array {
+        match self.smt.get(&u8a_to_h256(key)) {
+            Ok(v) => Uint8Array::from(v.as_slice()),
+            Err(err) => throw_str(&format!("smt get failed, err: {err:?}, key: {key:?}")),
+        }
+    }
+
+    /// Delete `key` by updating it to the zero value.
+    pub fn delete(&mut self, key: &Uint8Array) {
+        if let Err(err) = self.smt.update(u8a_to_h256(key), H256::zero()) {
+            throw_str(&format!("smt delete failed, Err: {err:?}, key: {key:?}"));
+        }
+    }
+
+    /// Serialize every leaf as `key(32) || value(32)` into a compact blob so a
+    /// browser client can persist the tree and rebuild it with `import` instead
+    /// of replaying every `update` at the JS layer.
+    pub fn export(&self) -> Uint8Array {
+        let leaves = self.smt.store().leaves_map();
+        let mut buffer = Vec::with_capacity(leaves.len() * 64);
+        for (key, value) in leaves.iter() {
+            buffer.extend_from_slice(key.as_slice());
+            buffer.extend_from_slice(value.as_slice());
+        }
+        Uint8Array::from(buffer.as_slice())
+    }
+
+    /// Rebuild a tree from a blob produced by `export`.
+    pub fn import(bytes: &Uint8Array) -> CkbSmt {
+        let bytes = bytes.to_vec();
+        if bytes.len() % 64 != 0 {
+            throw_str("smt import failed: blob length must be a multiple of 64");
+        }
+        let leaves: Vec<(H256, H256)> = bytes
+            .chunks_exact(64)
+            .map(|chunk| {
+                let key: [u8; 32] = chunk[..32].try_into().unwrap();
+                let value: [u8; 32] = chunk[32..].try_into().unwrap();
+                (key.into(), value.into())
+            })
+            .collect();
+        let mut smt = CkbSmt::default();
+        if let Err(err) = smt.smt.update_all(leaves) {
+            throw_str(&format!("smt import failed, err: {err:?}"));
+        }
+        smt
+    }
+
     pub fn get_proof(&self, keys: Vec<Uint8Array>) -> Uint8Array {
         let keys: Vec<H256> = keys.into_iter().map(|f| u8a_to_h256(&f)).collect();
         let proof = match self.smt.merkle_proof(keys.clone()) {